@@ -0,0 +1,57 @@
+//! Compares the stack-based polymer reducer against the original implementation on the real
+//! day 5 input. Run with `cargo bench --bench polymer`.
+
+extern crate advent_of_code_2018;
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use advent_of_code_2018::bench::{millis, Stats};
+use advent_of_code_2018::day5;
+
+fn measure<F: Fn() -> usize>(runs: usize, f: F) -> (usize, Stats) {
+    let mut samples: Vec<Duration> = Vec::new();
+    let mut result = 0;
+
+    for _ in 0..runs {
+        let start = Instant::now();
+        result = f();
+        samples.push(start.elapsed());
+    }
+
+    (result, Stats::from_samples(&samples).unwrap())
+}
+
+fn compare<R: Fn() -> usize, S: Fn() -> usize>(name: &str, runs: usize, reference: R, stack: S) {
+    let (reference_len, reference_stats) = measure(runs, reference);
+    let (stack_len, stack_stats) = measure(runs, stack);
+
+    assert_eq!(reference_len, stack_len, "{} results differ", name);
+
+    println!(
+        "{:<10} reference {:>10.3}ms  stack {:>8.3}ms  speed-up {:>7.1}x",
+        name,
+        millis(reference_stats.median),
+        millis(stack_stats.median),
+        millis(reference_stats.median) / millis(stack_stats.median),
+    );
+}
+
+fn main() {
+    let polymer = fs::read_to_string("resources/day5.txt").expect("Failed to load resources/day5.txt");
+    let polymer = polymer.trim();
+
+    compare(
+        "collapse",
+        10,
+        || day5::reference::collapse_polymer(polymer).len(),
+        || day5::collapse_polymer(polymer).len(),
+    );
+
+    compare(
+        "remove",
+        3,
+        || day5::reference::remove_best_unit_and_collapse(polymer).len(),
+        || day5::remove_best_unit_and_collapse(polymer).len(),
+    );
+}
@@ -0,0 +1,221 @@
+//! Recorded answers for the real inputs, stored in a small subset of TOML:
+//!
+//! ```toml
+//! [day1]
+//! part1 = "556"
+//! part2 = "448"
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use error::{Error, ParseError};
+
+pub const DEFAULT_PATH: &str = "resources/answers.toml";
+
+/// Where a profile's answers are kept: `answers.toml` beside its inputs in `dir`, or the default
+/// file when there is no profile.
+pub fn profile_path(dir: &str, profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => Path::new(dir).join(profile).join("answers.toml").to_string_lossy().into_owned(),
+        None => DEFAULT_PATH.to_string(),
+    }
+}
+
+/// Expected answers keyed by day and part.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Answers {
+    entries: BTreeMap<(u8, u8), String>,
+}
+
+/// The outcome of checking one part against its recorded answer.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail { expected: String },
+    Unknown,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail { ref expected } => write!(f, "FAIL (expected {})", expected),
+            Status::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+fn parse_section(line: &str) -> Result<u8, String> {
+    line.trim_start_matches("[day")
+        .trim_end_matches(']')
+        .parse()
+        .map_err(|_| "expected a section like [day1]".to_string())
+}
+
+fn parse_string(value: &str) -> Result<String, String> {
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Err("expected a quoted string".to_string());
+    }
+
+    let mut out = String::new();
+    let mut chars = value[1..value.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match (c, c == '\\') {
+            (_, true) => match chars.next() {
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                _ => return Err("unsupported escape sequence".to_string()),
+            },
+            ('"', _) => return Err("unescaped quote".to_string()),
+            (c, _) => out.push(c),
+        }
+    }
+
+    Ok(out)
+}
+
+fn parse_entry(line: &str) -> Result<(u8, String), String> {
+    let mut split = line.splitn(2, '=');
+    let key = split.next().unwrap_or("").trim();
+    let value = split.next().ok_or_else(|| "expected 'partN = \"answer\"'".to_string())?.trim();
+
+    let part = match key {
+        "part1" => 1,
+        "part2" => 2,
+        _ => return Err(format!("unknown key '{}'", key)),
+    };
+
+    Ok((part, parse_string(value)?))
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Answers {
+    pub fn parse(input: &str) -> Result<Answers, ParseError> {
+        let mut answers = Answers::default();
+        let mut day = None;
+
+        for (i, raw) in input.lines().enumerate() {
+            let line = raw.trim();
+            let error = |reason: String| ParseError::new(i + 1, raw, &reason);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                day = Some(parse_section(line).map_err(error)?);
+                continue;
+            }
+
+            let current = day.ok_or_else(|| error("answer outside of a [dayN] section".to_string()))?;
+            let (part, value) = parse_entry(line).map_err(error)?;
+            answers.set(current, part, &value);
+        }
+
+        Ok(answers)
+    }
+
+    /// Loads answers from `path`, treating a missing file as having no recorded answers.
+    pub fn load(path: &str) -> Result<Answers, Error> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Answers::parse(&contents).map_err(|source| Error::Answers { path: path.to_string(), source }),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(source) => Err(Error::Io { path: path.to_string(), source }),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        fs::write(path, self.to_toml()).map_err(|source| Error::Io { path: path.to_string(), source })
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.entries.get(&(day, part)).map(|s| s.as_str())
+    }
+
+    pub fn set(&mut self, day: u8, part: u8, value: &str) {
+        self.entries.insert((day, part), value.to_string());
+    }
+
+    pub fn check(&self, day: u8, part: u8, actual: &str) -> Status {
+        match self.get(day, part) {
+            Some(expected) if expected == actual => Status::Pass,
+            Some(expected) => Status::Fail { expected: expected.to_string() },
+            None => Status::Unknown,
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        let mut current = None;
+
+        for (&(day, part), value) in self.entries.iter() {
+            if current != Some(day) {
+                if current.is_some() {
+                    out.push('\n');
+                }
+                out.push_str(&format!("[day{}]\n", day));
+                current = Some(day);
+            }
+            out.push_str(&format!("part{} = {}\n", part, quote(value)));
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_answers() {
+        let answers = Answers::parse("# comment\n[day1]\npart1 = \"556\"\n\n[day2]\npart2 = \"a\\\"b\"\n").unwrap();
+
+        assert_eq!(Some("556"), answers.get(1, 1));
+        assert_eq!(None, answers.get(1, 2));
+        assert_eq!(Some("a\"b"), answers.get(2, 2));
+    }
+
+    #[test]
+    fn rejects_bad_answers() {
+        assert_eq!(1, Answers::parse("part1 = \"1\"").unwrap_err().line);
+        assert_eq!(2, Answers::parse("[day1]\npart3 = \"1\"").unwrap_err().line);
+        assert_eq!(2, Answers::parse("[day1]\npart1 = 1").unwrap_err().line);
+        assert_eq!(1, Answers::parse("[dayone]").unwrap_err().line);
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let mut answers = Answers::default();
+        answers.set(2, 1, "7808");
+        answers.set(1, 2, "back\\slash");
+        answers.set(1, 1, "556");
+
+        let toml = answers.to_toml();
+        assert_eq!("[day1]\npart1 = \"556\"\npart2 = \"back\\\\slash\"\n\n[day2]\npart1 = \"7808\"\n", toml);
+        assert_eq!(answers, Answers::parse(&toml).unwrap());
+    }
+
+    #[test]
+    fn should_keep_answers_per_profile() {
+        assert_eq!(DEFAULT_PATH, profile_path("inputs", None));
+        assert_eq!(Path::new("inputs").join("alice").join("answers.toml").to_string_lossy(), profile_path("inputs", Some("alice")));
+    }
+
+    #[test]
+    fn checks_answers() {
+        let mut answers = Answers::default();
+        answers.set(1, 1, "556");
+
+        assert_eq!(Status::Pass, answers.check(1, 1, "556"));
+        assert_eq!(Status::Fail { expected: "556".to_string() }, answers.check(1, 1, "557"));
+        assert_eq!(Status::Unknown, answers.check(1, 2, "448"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Repeated timing of a day's parse and parts, for comparing performance across changes.
+
+use std::time::Duration;
+
+use error::ParseError;
+use solution::Day;
+
+/// Summary of a set of timing samples.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    /// Summarises `samples`, or returns `None` if there are none.
+    pub fn from_samples(samples: &[Duration]) -> Option<Stats> {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+
+        let mid = sorted.len() / 2;
+        let median = match sorted.len() {
+            0 => return None,
+            n if n % 2 == 0 => (sorted[mid - 1] + sorted[mid]) / 2,
+            _ => sorted[mid],
+        };
+
+        Some(Stats { min: sorted[0], median, max: sorted[sorted.len() - 1] })
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "{{\"min_ms\":{:.6},\"median_ms\":{:.6},\"max_ms\":{:.6}}}",
+            millis(self.min), millis(self.median), millis(self.max)
+        )
+    }
+}
+
+/// Timings for one part across all runs.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PartBench {
+    pub part: u8,
+    pub stats: Stats,
+}
+
+/// Timings for one day across all runs.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DayBench {
+    pub day: u8,
+    pub runs: usize,
+    pub parse: Stats,
+    pub parts: Vec<PartBench>,
+}
+
+pub fn millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1000.0 + f64::from(duration.subsec_nanos()) / 1_000_000.0
+}
+
+/// Runs `solution` against `input` `runs` times, collecting timings for parsing and each part.
+pub fn bench_day(solution: &dyn Day, input: &str, part: Option<u8>, runs: usize) -> Result<DayBench, ParseError> {
+    let mut parse_samples = Vec::new();
+    let mut part_samples: Vec<(u8, Vec<Duration>)> = Vec::new();
+
+    for _ in 0..runs.max(1) {
+        let result = solution.run(input, part)?;
+        parse_samples.push(result.parse_duration);
+
+        for part_result in result.parts {
+            match part_samples.iter_mut().find(|(p, _)| *p == part_result.part) {
+                Some((_, samples)) => samples.push(part_result.duration),
+                None => part_samples.push((part_result.part, vec![part_result.duration])),
+            }
+        }
+    }
+
+    Ok(DayBench {
+        day: solution.day(),
+        runs: parse_samples.len(),
+        parse: Stats::from_samples(&parse_samples).unwrap(),
+        parts: part_samples
+            .iter()
+            .map(|(part, samples)| PartBench { part: *part, stats: Stats::from_samples(samples).unwrap() })
+            .collect(),
+    })
+}
+
+/// Serialises benchmark results as a JSON array, one object per day.
+pub fn to_json(results: &[DayBench]) -> String {
+    let days: Vec<String> = results
+        .iter()
+        .map(|day| {
+            let parts: Vec<String> = day.parts
+                .iter()
+                .map(|p| format!("{{\"part\":{},\"time\":{}}}", p.part, p.stats.to_json()))
+                .collect();
+
+            format!(
+                "{{\"day\":{},\"runs\":{},\"parse\":{},\"parts\":[{}]}}",
+                day.day, day.runs, day.parse.to_json(), parts.join(",")
+            )
+        })
+        .collect();
+
+    format!("[{}]\n", days.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn should_summarise_samples() {
+        assert_eq!(None, Stats::from_samples(&[]));
+        assert_eq!(
+            Some(Stats { min: ms(1), median: ms(3), max: ms(7) }),
+            Stats::from_samples(&[ms(7), ms(1), ms(3)])
+        );
+        assert_eq!(
+            Some(Stats { min: ms(1), median: ms(4), max: ms(7) }),
+            Stats::from_samples(&[ms(7), ms(1), ms(3), ms(5)])
+        );
+    }
+
+    #[test]
+    fn should_serialise_to_json() {
+        let stats = Stats { min: ms(1), median: ms(2), max: ms(3) };
+        let results = vec![DayBench { day: 1, runs: 3, parse: stats, parts: vec![PartBench { part: 1, stats }] }];
+
+        assert_eq!(
+            "[{\"day\":1,\"runs\":3,\
+            \"parse\":{\"min_ms\":1.000000,\"median_ms\":2.000000,\"max_ms\":3.000000},\
+            \"parts\":[{\"part\":1,\"time\":{\"min_ms\":1.000000,\"median_ms\":2.000000,\"max_ms\":3.000000}}]}]\n",
+            to_json(&results)
+        );
+    }
+}
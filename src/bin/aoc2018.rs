@@ -0,0 +1,53 @@
+extern crate advent_of_code_2018;
+
+use std::env;
+use std::io::{stdin,stdout,Write};
+use std::process;
+
+use advent_of_code_2018::cli::{self, Command, Days, RunOptions};
+use advent_of_code_2018::inputs::Inputs;
+use advent_of_code_2018::output::Format;
+use advent_of_code_2018::runner;
+
+fn prompt_for_day() -> i32 {
+    let mut buffer = String::new();
+    print!("Please enter the day: ");
+    let _= stdout().flush();
+    stdin().read_line(&mut buffer).expect("Failed to read input");
+
+    match buffer.trim().parse::<u8>() {
+        Ok(day) => runner::run(&RunOptions { days: Days::Day(day), part: None, inputs: Inputs::default(), format: Format::Text }),
+        _ => {
+            eprintln!("Input was not a valid day '{}'", buffer.trim());
+            1
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let exit_code = if args.is_empty() {
+        prompt_for_day()
+    } else {
+        match cli::parse_args(&args) {
+            Ok(Command::Run(options)) => runner::run(&options),
+            Ok(Command::Verify(options)) => runner::verify(&options),
+            Ok(Command::Bench(options)) => runner::bench(&options),
+            Ok(Command::Trace(options)) => runner::trace(&options),
+            Ok(Command::Analyse(options)) => runner::analyse(&options),
+            Ok(Command::Cluster(options)) => runner::cluster(&options),
+            Ok(Command::Coverage(options)) => runner::coverage(&options),
+            Ok(Command::Help) => {
+                println!("{}", cli::USAGE);
+                0
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                2
+            }
+        }
+    };
+
+    process::exit(exit_code);
+}
@@ -0,0 +1,600 @@
+//! Command-line argument parsing for the `aoc2018` runner.
+
+use std::fmt;
+use std::str::FromStr;
+
+use day3::Engine;
+use inputs::{Inputs, Source};
+use output::Format;
+
+/// Help text printed by `aoc2018 help` and after usage errors.
+pub const USAGE: &str = "Usage:
+    aoc2018                                     prompt for a day interactively
+    aoc2018 run --day <n> [--part <1|2>] [--input <path|->] [--format <text|json|csv>]
+    aoc2018 run --all [--part <1|2>] [--input-dir <dir>] [--profile <name>[,<name>...]] [--format <text|json|csv>]
+    aoc2018 verify [--day <n>] [<inputs>] [--answers <path>] [--record]
+    aoc2018 bench [--day <n>] [--part <1|2>] [<inputs>] [--runs <n>] [--json <path>]
+    aoc2018 trace [<inputs> | --polymer <units>] [--rules <path>] [--limit <n>]
+    aoc2018 analyse [<inputs>] [--repeats <n>] [--export <series|repeats|histogram>]
+    aoc2018 cluster [<inputs>] [--format <text|json|dot>] [--min-size <n>]
+    aoc2018 coverage [<inputs>] [--engine <cells|sweep>] [--depths]
+    aoc2018 help
+
+Every command reading puzzle input accepts <inputs>:
+    --input <path|->                            read a single day's input from a file or stdin
+    --input-dir <dir>                           look for inputs in <dir> rather than resources
+    --profile <name>[,<name>...]                read <dir>/<name>/dayN.txt for each profile
+
+verify keeps each profile's answers in <dir>/<name>/answers.toml unless --answers is given.";
+
+/// A parsed command line.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Command {
+    Run(RunOptions),
+    Verify(VerifyOptions),
+    Bench(BenchOptions),
+    Trace(TraceOptions),
+    Analyse(AnalyseOptions),
+    Cluster(ClusterOptions),
+    Coverage(CoverageOptions),
+    Help,
+}
+
+/// Which days a run covers.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Days {
+    All,
+    Day(u8),
+}
+
+/// Options for the `run` command. Runs both parts unless `part` is given.
+#[derive(Debug, Eq, PartialEq)]
+pub struct RunOptions {
+    pub days: Days,
+    pub part: Option<u8>,
+    pub inputs: Inputs,
+    pub format: Format,
+}
+
+/// Options for the `verify` command. Verifies every day unless `--day` is given, and with
+/// `record` saves the current answers as the new baseline. Answers are kept in `answers` when
+/// given, otherwise per profile under the input directory, or in the default file.
+#[derive(Debug, Eq, PartialEq)]
+pub struct VerifyOptions {
+    pub days: Days,
+    pub inputs: Inputs,
+    pub answers: Option<String>,
+    pub record: bool,
+}
+
+/// Options for the `bench` command. Benchmarks every day unless `--day` is given.
+#[derive(Debug, Eq, PartialEq)]
+pub struct BenchOptions {
+    pub days: Days,
+    pub part: Option<u8>,
+    pub inputs: Inputs,
+    pub runs: usize,
+    pub json: Option<String>,
+}
+
+/// Options for the `trace` command, which replays a day 5 polymer collapse. Polymers up to
+/// `limit` units are shown step by step, longer ones are summarised.
+#[derive(Debug, Eq, PartialEq)]
+pub struct TraceOptions {
+    pub inputs: Inputs,
+    /// Units to trace instead of reading any input.
+    pub polymer: Option<String>,
+    /// A file of reacting pairs to use instead of the puzzle's rule.
+    pub rules: Option<String>,
+    pub limit: usize,
+}
+
+/// A table of the day 1 analysis to export as CSV.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Table {
+    Series,
+    Repeats,
+    Histogram,
+}
+
+impl FromStr for Table {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Table, String> {
+        match s {
+            "series" => Ok(Table::Series),
+            "repeats" => Ok(Table::Repeats),
+            "histogram" => Ok(Table::Histogram),
+            _ => Err(format!("Unknown table '{}', expected series, repeats or histogram", s)),
+        }
+    }
+}
+
+/// Options for the `analyse` command, which summarises the day 1 frequency changes, or with
+/// `export` prints one table of the analysis as CSV.
+#[derive(Debug, Eq, PartialEq)]
+pub struct AnalyseOptions {
+    pub inputs: Inputs,
+    pub repeats: usize,
+    pub export: Option<Table>,
+}
+
+/// How the `cluster` command prints day 2 box ID clusters.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ClusterFormat {
+    Text,
+    Json,
+    Dot,
+}
+
+impl FromStr for ClusterFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ClusterFormat, String> {
+        match s {
+            "text" => Ok(ClusterFormat::Text),
+            "json" => Ok(ClusterFormat::Json),
+            "dot" => Ok(ClusterFormat::Dot),
+            _ => Err(format!("Unknown format '{}', expected text, json or dot", s)),
+        }
+    }
+}
+
+/// Options for the `cluster` command, which groups day 2 box IDs linked by single character
+/// differences. Only clusters of at least `min_size` IDs are shown.
+#[derive(Debug, Eq, PartialEq)]
+pub struct ClusterOptions {
+    pub inputs: Inputs,
+    pub format: ClusterFormat,
+    pub min_size: usize,
+}
+
+/// Options for the `coverage` command, which lays the day 3 claims over the fabric with `engine`.
+/// With `depths` it also shows the area covered by each number of claims.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CoverageOptions {
+    pub inputs: Inputs,
+    pub engine: Engine,
+    pub depths: bool,
+}
+
+/// Invalid arguments, displayed alongside the usage text.
+#[derive(Debug, Eq, PartialEq)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\n\n{}", self.0, USAGE)
+    }
+}
+
+fn usage_error<T>(message: String) -> Result<T, UsageError> {
+    Err(UsageError(message))
+}
+
+fn expect_value<'a>(flag: &str, value: Option<&'a String>) -> Result<&'a String, UsageError> {
+    value.ok_or_else(|| UsageError(format!("{} expects a value", flag)))
+}
+
+fn parse_number<T: FromStr>(flag: &str, value: Option<&String>) -> Result<T, UsageError> {
+    let value = expect_value(flag, value)?;
+    value.parse::<T>().or_else(|_| usage_error(format!("{} expects a number, got '{}'", flag, value)))
+}
+
+fn parse_part(flag: &str, value: Option<&String>) -> Result<u8, UsageError> {
+    match parse_number(flag, value)? {
+        n @ 1..=2 => Ok(n),
+        n => usage_error(format!("--part must be 1 or 2, got '{}'", n)),
+    }
+}
+
+/// Applies one of the flags every command uses to locate its input.
+fn parse_input_flag(inputs: &mut Inputs, flag: &str, value: Option<&String>) -> Result<(), UsageError> {
+    let value = expect_value(flag, value)?;
+
+    match flag {
+        "--input-dir" => inputs.dir = value.to_string(),
+        "--profile" => inputs.profiles.extend(value.split(',').filter(|p| !p.is_empty()).map(|p| p.to_string())),
+        _ => inputs.input = Some(Source::from_arg(value)),
+    }
+
+    Ok(())
+}
+
+fn check_inputs(inputs: &Inputs, days: &Days) -> Result<(), UsageError> {
+    if inputs.input.is_some() && *days == Days::All {
+        return usage_error("--input can only be used with a single --day".to_string());
+    }
+
+    if inputs.input.is_some() && !inputs.profiles.is_empty() {
+        return usage_error("--input and --profile cannot be combined".to_string());
+    }
+
+    Ok(())
+}
+
+fn parse_run(args: &[String]) -> Result<Command, UsageError> {
+    let mut day = None;
+    let mut all = false;
+    let mut part = None;
+    let mut inputs = Inputs::default();
+    let mut format = Format::Text;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--day" | "-d" => day = Some(parse_number(arg, iter.next())?),
+            "--part" | "-p" => part = Some(parse_part(arg, iter.next())?),
+            "--input" | "-i" | "--input-dir" | "--profile" => parse_input_flag(&mut inputs, arg, iter.next())?,
+            "--format" | "-f" => format = expect_value(arg, iter.next())?.parse().map_err(UsageError)?,
+            "--all" | "-a" => all = true,
+            other => return usage_error(format!("Unexpected argument '{}'", other)),
+        }
+    }
+
+    let days = match (day, all) {
+        (Some(_), true) => return usage_error("--day and --all cannot be combined".to_string()),
+        (Some(n), false) => Days::Day(n),
+        (None, true) => Days::All,
+        (None, false) => return usage_error("Either --day or --all is required".to_string()),
+    };
+
+    check_inputs(&inputs, &days)?;
+
+    Ok(Command::Run(RunOptions { days, part, inputs, format }))
+}
+
+fn parse_verify(args: &[String]) -> Result<Command, UsageError> {
+    let mut days = Days::All;
+    let mut inputs = Inputs::default();
+    let mut answers = None;
+    let mut record = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--day" | "-d" => days = Days::Day(parse_number(arg, iter.next())?),
+            "--all" | "-a" => days = Days::All,
+            "--input" | "-i" | "--input-dir" | "--profile" => parse_input_flag(&mut inputs, arg, iter.next())?,
+            "--answers" => answers = Some(expect_value(arg, iter.next())?.to_string()),
+            "--record" => record = true,
+            other => return usage_error(format!("Unexpected argument '{}'", other)),
+        }
+    }
+
+    check_inputs(&inputs, &days)?;
+
+    if answers.is_some() && inputs.profiles.len() > 1 {
+        return usage_error("--answers cannot be shared by several profiles".to_string());
+    }
+
+    Ok(Command::Verify(VerifyOptions { days, inputs, answers, record }))
+}
+
+fn parse_bench(args: &[String]) -> Result<Command, UsageError> {
+    let mut days = Days::All;
+    let mut part = None;
+    let mut inputs = Inputs::default();
+    let mut runs = 10;
+    let mut json = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--day" | "-d" => days = Days::Day(parse_number(arg, iter.next())?),
+            "--all" | "-a" => days = Days::All,
+            "--part" | "-p" => part = Some(parse_part(arg, iter.next())?),
+            "--input" | "-i" | "--input-dir" | "--profile" => parse_input_flag(&mut inputs, arg, iter.next())?,
+            "--runs" | "-n" => runs = parse_number(arg, iter.next())?,
+            "--json" => json = Some(expect_value(arg, iter.next())?.to_string()),
+            other => return usage_error(format!("Unexpected argument '{}'", other)),
+        }
+    }
+
+    if runs == 0 {
+        return usage_error("--runs must be at least 1".to_string());
+    }
+
+    check_inputs(&inputs, &days)?;
+
+    Ok(Command::Bench(BenchOptions { days, part, inputs, runs, json }))
+}
+
+fn parse_trace(args: &[String]) -> Result<Command, UsageError> {
+    let mut inputs = Inputs::default();
+    let mut polymer = None;
+    let mut rules = None;
+    let mut limit = 80;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input" | "-i" | "--input-dir" | "--profile" => parse_input_flag(&mut inputs, arg, iter.next())?,
+            "--polymer" => polymer = Some(expect_value(arg, iter.next())?.to_string()),
+            "--rules" => rules = Some(expect_value(arg, iter.next())?.to_string()),
+            "--limit" => limit = parse_number(arg, iter.next())?,
+            other => return usage_error(format!("Unexpected argument '{}'", other)),
+        }
+    }
+
+    if polymer.is_some() && inputs != Inputs::default() {
+        return usage_error("--polymer cannot be combined with --input, --input-dir or --profile".to_string());
+    }
+
+    check_inputs(&inputs, &Days::Day(5))?;
+
+    Ok(Command::Trace(TraceOptions { inputs, polymer, rules, limit }))
+}
+
+fn parse_analyse(args: &[String]) -> Result<Command, UsageError> {
+    let mut inputs = Inputs::default();
+    let mut repeats = 10;
+    let mut export = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input" | "-i" | "--input-dir" | "--profile" => parse_input_flag(&mut inputs, arg, iter.next())?,
+            "--repeats" | "-n" => repeats = parse_number(arg, iter.next())?,
+            "--export" => export = Some(expect_value(arg, iter.next())?.parse().map_err(UsageError)?),
+            other => return usage_error(format!("Unexpected argument '{}'", other)),
+        }
+    }
+
+    check_inputs(&inputs, &Days::Day(1))?;
+
+    Ok(Command::Analyse(AnalyseOptions { inputs, repeats, export }))
+}
+
+fn parse_cluster(args: &[String]) -> Result<Command, UsageError> {
+    let mut inputs = Inputs::default();
+    let mut format = ClusterFormat::Text;
+    let mut min_size = 2;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input" | "-i" | "--input-dir" | "--profile" => parse_input_flag(&mut inputs, arg, iter.next())?,
+            "--format" | "-f" => format = expect_value(arg, iter.next())?.parse().map_err(UsageError)?,
+            "--min-size" => min_size = parse_number(arg, iter.next())?,
+            other => return usage_error(format!("Unexpected argument '{}'", other)),
+        }
+    }
+
+    check_inputs(&inputs, &Days::Day(2))?;
+
+    Ok(Command::Cluster(ClusterOptions { inputs, format, min_size }))
+}
+
+fn parse_coverage(args: &[String]) -> Result<Command, UsageError> {
+    let mut inputs = Inputs::default();
+    let mut engine = Engine::default();
+    let mut depths = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--input" | "-i" | "--input-dir" | "--profile" => parse_input_flag(&mut inputs, arg, iter.next())?,
+            "--engine" => engine = expect_value(arg, iter.next())?.parse().map_err(UsageError)?,
+            "--depths" => depths = true,
+            other => return usage_error(format!("Unexpected argument '{}'", other)),
+        }
+    }
+
+    check_inputs(&inputs, &Days::Day(3))?;
+
+    Ok(Command::Coverage(CoverageOptions { inputs, engine, depths }))
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args(args: &[String]) -> Result<Command, UsageError> {
+    match args.first().map(|a| a.as_str()) {
+        Some("help") | Some("--help") | Some("-h") => Ok(Command::Help),
+        Some("run") => parse_run(&args[1..]),
+        Some("verify") => parse_verify(&args[1..]),
+        Some("bench") => parse_bench(&args[1..]),
+        Some("trace") => parse_trace(&args[1..]),
+        Some("analyse") | Some("analyze") => parse_analyse(&args[1..]),
+        Some("cluster") => parse_cluster(&args[1..]),
+        Some("coverage") => parse_coverage(&args[1..]),
+        Some(a) if a.starts_with('-') => parse_run(args),
+        Some(other) => usage_error(format!("Unknown command '{}'", other)),
+        None => usage_error("No command given".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|x| x.to_string()).collect()
+    }
+
+    fn input(source: Source) -> Inputs {
+        Inputs { input: Some(source), ..Inputs::default() }
+    }
+
+    #[test]
+    fn can_parse_run() {
+        assert_eq!(
+            Ok(Command::Run(RunOptions { days: Days::Day(3), part: Some(2), inputs: input(Source::File("in.txt".to_string())), format: Format::Text })),
+            parse_args(&args("run --day 3 --part 2 --input in.txt"))
+        );
+
+        assert_eq!(
+            Ok(Command::Run(RunOptions { days: Days::Day(5), part: None, inputs: input(Source::Stdin), format: Format::Json })),
+            parse_args(&args("--day 5 --input - --format json"))
+        );
+
+        assert_eq!(
+            Ok(Command::Run(RunOptions { days: Days::All, part: Some(1), inputs: Inputs::default(), format: Format::Text })),
+            parse_args(&args("run --all -p 1"))
+        );
+
+        assert_eq!(
+            Ok(Command::Run(RunOptions {
+                days: Days::All,
+                part: None,
+                inputs: Inputs {
+                    dir: "inputs".to_string(),
+                    profiles: vec!["alice".to_string(), "bob".to_string(), "carol".to_string()],
+                    input: None,
+                },
+                format: Format::Text,
+            })),
+            parse_args(&args("run --all --input-dir inputs --profile alice,bob --profile carol"))
+        );
+
+        assert_eq!(Ok(Command::Help), parse_args(&args("help")));
+    }
+
+    #[test]
+    fn can_parse_verify() {
+        assert_eq!(
+            Ok(Command::Verify(VerifyOptions { days: Days::All, inputs: Inputs::default(), answers: None, record: false })),
+            parse_args(&args("verify"))
+        );
+
+        assert_eq!(
+            Ok(Command::Verify(VerifyOptions { days: Days::Day(4), inputs: Inputs::default(), answers: Some("mine.toml".to_string()), record: true })),
+            parse_args(&args("verify --day 4 --answers mine.toml --record"))
+        );
+
+        assert_eq!(
+            Ok(Command::Verify(VerifyOptions {
+                days: Days::All,
+                inputs: Inputs { dir: "inputs".to_string(), profiles: vec!["alice".to_string()], input: None },
+                answers: Some("alice.toml".to_string()),
+                record: false,
+            })),
+            parse_args(&args("verify --input-dir inputs --profile alice --answers alice.toml"))
+        );
+
+        assert_eq!(
+            Ok(Command::Verify(VerifyOptions {
+                days: Days::All,
+                inputs: Inputs { profiles: vec!["alice".to_string(), "bob".to_string()], ..Inputs::default() },
+                answers: None,
+                record: true,
+            })),
+            parse_args(&args("verify --profile alice,bob --record"))
+        );
+
+        assert!(parse_args(&args("verify --input in.txt")).is_err());
+        assert!(parse_args(&args("verify --profile alice,bob --answers shared.toml")).is_err());
+
+        assert!(parse_args(&args("verify --part 1")).is_err());
+    }
+
+    #[test]
+    fn can_parse_bench() {
+        assert_eq!(
+            Ok(Command::Bench(BenchOptions { days: Days::All, part: None, inputs: Inputs::default(), runs: 10, json: None })),
+            parse_args(&args("bench"))
+        );
+
+        assert_eq!(
+            Ok(Command::Bench(BenchOptions {
+                days: Days::Day(5),
+                part: Some(2),
+                inputs: input(Source::File("big.txt".to_string())),
+                runs: 500,
+                json: Some("out.json".to_string()),
+            })),
+            parse_args(&args("bench --day 5 --part 2 --input big.txt --runs 500 --json out.json"))
+        );
+
+        assert!(parse_args(&args("bench --runs 0")).is_err());
+        assert!(parse_args(&args("bench --runs many")).is_err());
+    }
+
+    #[test]
+    fn can_parse_trace() {
+        assert_eq!(
+            Ok(Command::Trace(TraceOptions { inputs: Inputs::default(), polymer: None, rules: None, limit: 80 })),
+            parse_args(&args("trace"))
+        );
+
+        assert_eq!(
+            Ok(Command::Trace(TraceOptions { inputs: input(Source::Stdin), polymer: None, rules: Some("pairs.txt".to_string()), limit: 10 })),
+            parse_args(&args("trace --input - --rules pairs.txt --limit 10"))
+        );
+
+        assert_eq!(
+            Ok(Command::Trace(TraceOptions { inputs: Inputs::default(), polymer: Some("aAbB".to_string()), rules: None, limit: 80 })),
+            parse_args(&args("trace --polymer aAbB"))
+        );
+
+        assert!(parse_args(&args("trace --input - --polymer aAbB")).is_err());
+        assert!(parse_args(&args("trace --profile alice --polymer aAbB")).is_err());
+    }
+
+    #[test]
+    fn can_parse_analyse() {
+        assert_eq!(
+            Ok(Command::Analyse(AnalyseOptions { inputs: Inputs::default(), repeats: 10, export: None })),
+            parse_args(&args("analyse"))
+        );
+
+        assert_eq!(
+            Ok(Command::Analyse(AnalyseOptions { inputs: input(Source::Stdin), repeats: 3, export: Some(Table::Histogram) })),
+            parse_args(&args("analyze -i - --repeats 3 --export histogram"))
+        );
+
+        assert!(parse_args(&args("analyse --export chart")).is_err());
+    }
+
+    #[test]
+    fn can_parse_cluster() {
+        assert_eq!(
+            Ok(Command::Cluster(ClusterOptions { inputs: Inputs::default(), format: ClusterFormat::Text, min_size: 2 })),
+            parse_args(&args("cluster"))
+        );
+
+        assert_eq!(
+            Ok(Command::Cluster(ClusterOptions { inputs: input(Source::Stdin), format: ClusterFormat::Dot, min_size: 1 })),
+            parse_args(&args("cluster -i - --format dot --min-size 1"))
+        );
+
+        assert_eq!(
+            Ok(Command::Cluster(ClusterOptions {
+                inputs: Inputs { dir: "inputs".to_string(), profiles: vec!["bob".to_string()], input: None },
+                format: ClusterFormat::Text,
+                min_size: 2,
+            })),
+            parse_args(&args("cluster --input-dir inputs --profile bob"))
+        );
+
+        assert!(parse_args(&args("cluster --format csv")).is_err());
+    }
+
+    #[test]
+    fn can_parse_coverage() {
+        assert_eq!(
+            Ok(Command::Coverage(CoverageOptions { inputs: Inputs::default(), engine: Engine::Sweep, depths: false })),
+            parse_args(&args("coverage"))
+        );
+
+        assert_eq!(
+            Ok(Command::Coverage(CoverageOptions { inputs: input(Source::Stdin), engine: Engine::Cells, depths: true })),
+            parse_args(&args("coverage -i - --engine cells --depths"))
+        );
+
+        assert!(parse_args(&args("coverage --engine pixels")).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse_args(&args("run")).is_err());
+        assert!(parse_args(&args("run --day")).is_err());
+        assert!(parse_args(&args("run --day three")).is_err());
+        assert!(parse_args(&args("run --day 3 --part 3")).is_err());
+        assert!(parse_args(&args("run --day 3 --all")).is_err());
+        assert!(parse_args(&args("run --all --input in.txt")).is_err());
+        assert!(parse_args(&args("run --day 1 --input in.txt --profile alice")).is_err());
+        assert!(parse_args(&args("run --day 3 --verbose")).is_err());
+        assert!(parse_args(&args("run --day 3 --format xml")).is_err());
+        assert!(parse_args(&args("fly")).is_err());
+    }
+}
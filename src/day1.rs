@@ -1,46 +1,251 @@
-use std::collections::HashSet;
-
-fn parse_lines(lines: &Vec<String>) -> Vec<i32> {
-    lines
-        .iter()
-        .map(|x| x.parse::<i32>())
-        .flatten()
-        .collect()
+//! Day 1: Chronal Calibration.
+//!
+//! The frequency engine is generic over the [`Frequency`](frequency/trait.Frequency.html) type,
+//! and reports an [`Overflow`](frequency/struct.Overflow.html) rather than wrapping. Enable the
+//! `bigint` feature to track frequencies as arbitrary-precision `BigInt`s.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::fmt::Display;
+use std::io::Read;
+use std::str::FromStr;
+
+use error::{ParseError, SolveError};
+use helpers;
+use solution::{Answer, Solution};
+
+pub mod frequency;
+
+pub use self::frequency::{Frequency, Overflow};
+
+/// Parses one change per line, e.g. `+3` or `-2`, skipping blank lines.
+pub fn parse_changes<T, R: Read>(input: R) -> Result<Vec<T>, ParseError>
+    where T: FromStr, T::Err: Display
+{
+    helpers::lines(input).skip_blank().parse().collect()
+}
+
+/// The frequency before and after each change, starting from zero.
+pub fn running_frequencies<T: Frequency>(changes: &[T]) -> Result<Vec<T>, Overflow> {
+    let mut series = Vec::with_capacity(changes.len() + 1);
+    series.push(T::zero());
+
+    for (step, change) in changes.iter().enumerate() {
+        let next = series[step].checked_add(change).ok_or(Overflow { step: step as u64 + 1 })?;
+        series.push(next);
+    }
+
+    Ok(series)
+}
+
+/// The frequency after applying every change once.
+pub fn sum_changes<T: Frequency>(changes: &[T]) -> Result<T, Overflow> {
+    running_frequencies(changes).map(|mut series| series.pop().unwrap_or_else(T::zero))
 }
 
-fn sum_lines(lines: Vec<i32>) -> i32 {
-    lines.iter().sum()
+/// Where a frequency is first reached for the second time. The repeat happens on the pass
+/// through the changes numbered `iteration`, when applying the change at `index`, both from zero.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Repeat<T = i64> {
+    pub frequency: T,
+    pub iteration: usize,
+    pub index: usize,
 }
 
-fn find_first_repeat(lines: Vec<i32>) -> i32 {
-    let mut pos = 0;
-    let mut seen: HashSet<i32> = HashSet::new();
-    let mut running_total = 0;
+impl<T> Repeat<T> {
+    /// Builds the repeat for the frequency reached after `step` changes, counting from one.
+    fn at_step(frequency: T, step: u64, changes: usize) -> Repeat<T> {
+        let n = changes as u64;
+        Repeat { frequency, iteration: ((step - 1) / n) as usize, index: ((step - 1) % n) as usize }
+    }
+}
+
+/// Orders frequencies in the direction of the drift, so that later passes move forwards.
+fn along<T: Ord>(drift_sign: Ordering, a: &T, b: &T) -> Ordering {
+    match drift_sign {
+        Ordering::Less => b.cmp(a),
+        _ => a.cmp(b),
+    }
+}
+
+/// A pending repeat as (step, frequency, end of its run). A missing frequency marks the step
+/// where a run overflows.
+type Pending<T> = Reverse<(u64, Option<T>, Option<T>)>;
 
-    seen.insert(0);
+/// Frequencies in the order they are first reached for the second time. Created by
+/// [`repeats`](fn.repeats.html).
+///
+/// If a repeated frequency would leave the range of `T`, every repeat before it is listed,
+/// followed by the overflow.
+pub struct Repeats<T> {
+    queue: BinaryHeap<Pending<T>>,
+    changes: usize,
+    drift: T,
+}
+
+impl<T: Frequency> Iterator for Repeats<T> {
+    type Item = Result<Repeat<T>, Overflow>;
+
+    fn next(&mut self) -> Option<Result<Repeat<T>, Overflow>> {
+        let Reverse((step, value, end)) = self.queue.pop()?;
+        let value = match value {
+            Some(value) => value,
+            None => {
+                self.queue.clear();
+                return Some(Err(Overflow { step }));
+            }
+        };
+        let drift_sign = self.drift.cmp(&T::zero());
 
-    loop {
-        if pos == lines.len() {
-            pos = 0;
+        // A run ending inside the range of `T` never overflows, so only open runs can.
+        if drift_sign != Ordering::Equal {
+            let next = value.checked_add(&self.drift);
+            let within_run = match (&next, &end) {
+                (Some(next), Some(end)) => along(drift_sign, next, end) == Ordering::Less,
+                _ => true,
+            };
+            if within_run {
+                if let Some(step) = step.checked_add(self.changes as u64) {
+                    self.queue.push(Reverse((step, next, end)));
+                }
+            }
         }
 
-        running_total += lines[pos];
+        Some(Ok(Repeat::at_step(value, step, self.changes)))
+    }
+}
+
+/// Lists every repeated frequency when applying `changes` over and over, in the order each is
+/// reached for the second time.
+///
+/// After `k` full passes and `i` more changes the frequency is `prefix[i] + k * drift`, where
+/// `drift` is the sum of a pass. Two prefixes can only meet if they are congruent modulo the
+/// drift, so prefixes are grouped by remainder and sorted in the direction of the drift. A value
+/// is reached first from the nearest prefix behind it and second from the prefix before that,
+/// which gives each pair of neighbours a run of repeats spaced one pass apart.
+pub fn repeats<T: Frequency>(changes: &[T]) -> Result<Repeats<T>, Overflow> {
+    let n = changes.len();
+    let mut prefixes = running_frequencies(changes)?;
+    let drift = prefixes.pop().unwrap_or_else(T::zero);
+    let drift_sign = drift.cmp(&T::zero());
 
-        if seen.contains(&running_total) {
-            return running_total;
+    let mut groups: HashMap<T, Vec<(T, usize)>> = HashMap::new();
+    for (i, prefix) in prefixes.iter().enumerate() {
+        let key = match drift_sign {
+            Ordering::Equal => prefix.clone(),
+            // Only `MIN % -1` fails, and it leaves no remainder.
+            _ => prefix.checked_rem_euclid(&drift).unwrap_or_else(T::zero),
+        };
+        groups.entry(key).or_default().push((prefix.clone(), i));
+    }
+
+    let mut queue = BinaryHeap::new();
+    for group in groups.values_mut() {
+        group.sort_by(|a, b| along(drift_sign, &a.0, &b.0).then(b.1.cmp(&a.1)));
+
+        if drift_sign == Ordering::Equal && group.len() == 1 {
+            let (ref value, i) = group[0];
+            queue.push(Reverse(((i + n) as u64, Some(value.clone()), None)));
         }
 
-        seen.insert(running_total);
-        pos = pos + 1;
+        for b in 1..group.len() {
+            let (ref from, i) = group[b - 1];
+            let value = &group[b].0;
+            let end = group.get(b + 1).map(|next| next.0.clone());
+
+            if end.as_ref() != Some(value) {
+                let passes = match drift_sign {
+                    Ordering::Equal => Some(0),
+                    _ => from.passes(value, &drift),
+                };
+                let step = passes.and_then(|p| p.checked_mul(n as u64)).and_then(|s| s.checked_add(i as u64));
+
+                // Repeats more than u64::MAX changes away are never reached.
+                if let Some(step) = step {
+                    queue.push(Reverse((step, Some(value.clone()), end)));
+                }
+            }
+        }
     }
+
+    Ok(Repeats { queue, changes: n, drift })
 }
 
-pub fn part_1(lines: &Vec<String>) -> i32 {
-    sum_lines(parse_lines(lines))
+/// Finds the first frequency reached twice when applying `changes` over and over, or `None` if
+/// no frequency ever repeats.
+pub fn find_first_repeat<T: Frequency>(changes: &[T]) -> Result<Option<Repeat<T>>, Overflow> {
+    repeats(changes)?.next().transpose()
 }
 
-pub fn part_2(lines: &Vec<String>) -> i32 {
-    find_first_repeat(parse_lines(lines))
+/// A summary of how the frequency moves while applying the changes.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Analysis<T = i64> {
+    /// The frequency before and after each change of the first pass.
+    pub series: Vec<T>,
+    /// The lowest and highest frequencies in `series`.
+    pub min: T,
+    pub max: T,
+    /// How far the frequency moves over each full pass.
+    pub drift: T,
+    pub repeats: Vec<Repeat<T>>,
+    /// Set if a repeated frequency overflows before the requested number of repeats.
+    pub overflow: Option<Overflow>,
+    /// How often each change value occurs, by value.
+    pub histogram: Vec<(T, usize)>,
+}
+
+/// Analyses `changes`, listing up to `max_repeats` repeated frequencies.
+pub fn analyse<T: Frequency>(changes: &[T], max_repeats: usize) -> Result<Analysis<T>, Overflow> {
+    let series = running_frequencies(changes)?;
+
+    let mut histogram: BTreeMap<T, usize> = BTreeMap::new();
+    for change in changes {
+        *histogram.entry(change.clone()).or_insert(0) += 1;
+    }
+
+    let mut found = Vec::new();
+    let mut overflow = None;
+    for repeat in repeats(changes)?.take(max_repeats) {
+        match repeat {
+            Ok(repeat) => found.push(repeat),
+            Err(e) => overflow = Some(e),
+        }
+    }
+
+    Ok(Analysis {
+        min: series.iter().min().cloned().unwrap_or_else(T::zero),
+        max: series.iter().max().cloned().unwrap_or_else(T::zero),
+        drift: series.last().cloned().unwrap_or_else(T::zero),
+        repeats: found,
+        overflow,
+        histogram: histogram.into_iter().collect(),
+        series,
+    })
+}
+
+/// Registry entry for day 1. Frequencies are tracked as `i64`.
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = Vec<i64>;
+
+    fn day(&self) -> u8 { 1 }
+
+    fn parse(&self, input: &str) -> Result<Vec<i64>, ParseError> {
+        parse_changes(input.as_bytes())
+    }
+
+    fn part_1(&self, changes: &Vec<i64>) -> Result<Answer, SolveError> {
+        let frequency = sum_changes(changes).map_err(SolveError::new)?;
+        Ok(Answer::new("Resulting frequency", frequency))
+    }
+
+    fn part_2(&self, changes: &Vec<i64>) -> Result<Answer, SolveError> {
+        match find_first_repeat(changes).map_err(SolveError::new)? {
+            Some(repeat) => Ok(Answer::new("First repeat", repeat.frequency)),
+            None => Ok(Answer::new("First repeat", "none")),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -48,19 +253,155 @@ mod tests {
     use super::*;
 
     #[test]
-    fn should_sum_lines() {
-        assert_eq!(sum_lines(vec![1, -2, 3, 1]), 3);
-        assert_eq!(sum_lines(vec![1, 1, 1]), 3);
-        assert_eq!(sum_lines(vec![1, 1, -2]), 0);
-        assert_eq!(sum_lines(vec![-1, -2, -3]), -6);
+    fn should_parse_changes() {
+        assert_eq!(Ok(vec![1, -2, 3]), parse_changes::<i32, _>("+1\n-2\n\n+3\n".as_bytes()));
+        assert_eq!(Err(ParseError::new(3, "+x", "invalid digit found in string")), parse_changes::<i32, _>("+1\n-2\n+x".as_bytes()));
+        assert_eq!(Ok(vec![3_000_000_000i64]), parse_changes("+3000000000".as_bytes()));
+        assert!(parse_changes::<i32, _>("+3000000000".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn should_sum_changes() {
+        assert_eq!(sum_changes(&[1, -2, 3, 1]), Ok(3));
+        assert_eq!(sum_changes(&[1, 1, 1]), Ok(3));
+        assert_eq!(sum_changes(&[1, 1, -2]), Ok(0));
+        assert_eq!(sum_changes(&[-1, -2, -3]), Ok(-6));
+    }
+
+    #[test]
+    fn should_report_overflow() {
+        assert_eq!(Err(Overflow { step: 3 }), sum_changes(&[i32::MAX, -1, 2]));
+        assert_eq!(Ok(i64::from(i32::MAX) + 1), sum_changes(&[i64::from(i32::MAX), -1, 2]));
+        assert_eq!(Err(Overflow { step: 2 }), sum_changes(&[i128::MIN, -1]));
+        assert_eq!(Err(Overflow { step: 2 }), find_first_repeat(&[i32::MAX, 1]));
+
+        let changes = vec![i64::MAX, 1];
+        assert_eq!(Err(SolveError::new(Overflow { step: 2 })), Day1.part_1(&changes));
+        assert_eq!(Err(SolveError::new(Overflow { step: 2 })), Day1.part_2(&changes));
+    }
+
+    fn first_repeat(changes: &[i32]) -> Option<i32> {
+        find_first_repeat(changes).unwrap().map(|r| r.frequency)
     }
 
     #[test]
     fn should_find_first_repeat() {
-        assert_eq!(find_first_repeat(vec![1, -2, 3, 1]), 2);
-        assert_eq!(find_first_repeat(vec![1, -1]), 0);
-        assert_eq!(find_first_repeat(vec![3, 3, 4, -2, -4]), 10);
-        assert_eq!(find_first_repeat(vec![-6, 3, 8, 5, -6]), 5);
-        assert_eq!(find_first_repeat(vec![7, 7, -2, -7, -4]), 14);
+        assert_eq!(first_repeat(&[1, -2, 3, 1]), Some(2));
+        assert_eq!(first_repeat(&[1, -1]), Some(0));
+        assert_eq!(first_repeat(&[3, 3, 4, -2, -4]), Some(10));
+        assert_eq!(first_repeat(&[-6, 3, 8, 5, -6]), Some(5));
+        assert_eq!(first_repeat(&[7, 7, -2, -7, -4]), Some(14));
+    }
+
+    #[test]
+    fn should_report_where_repeat_happens() {
+        assert_eq!(Ok(Some(Repeat { frequency: 2, iteration: 1, index: 1 })), find_first_repeat(&[1, -2, 3, 1]));
+        assert_eq!(Ok(Some(Repeat { frequency: 0, iteration: 0, index: 1 })), find_first_repeat(&[1, -1]));
+        assert_eq!(Ok(Some(Repeat { frequency: 1, iteration: 0, index: 2 })), find_first_repeat(&[1, 1, -1]));
+        assert_eq!(Ok(Some(Repeat { frequency: 0, iteration: 0, index: 0 })), find_first_repeat(&[0]));
+
+        // Repeats keep the full width of the frequency type.
+        assert_eq!(Ok(Some(Repeat { frequency: 1i64 << 40, iteration: 0, index: 2 })), find_first_repeat(&[1 << 40, 1, -1]));
+    }
+
+    #[test]
+    fn should_detect_inputs_that_never_repeat() {
+        assert_eq!(Ok(None), find_first_repeat::<i64>(&[]));
+        assert_eq!(Ok(None), find_first_repeat(&[1, 2, 3]));
+        assert_eq!(Ok(None), find_first_repeat(&[3, -1]));
+        assert_eq!(Ok(None), find_first_repeat(&[i64::from(i32::MAX), i64::from(i32::MAX)]));
+
+        // The answer does not depend on how wide the frequency type is.
+        assert_eq!(Ok(None), find_first_repeat(&[1i32 << 30, -1]));
+        assert_eq!(Ok(None), find_first_repeat(&[-2i32, 1, -2]));
+        assert_eq!(Ok(None), find_first_repeat(&[-2i128, 1, -2]));
+    }
+
+    #[test]
+    fn should_list_repeats_before_overflow() {
+        // Falls by 2^29 each pass, repeating every other step until passing i32::MIN.
+        let repeat = |frequency, iteration| Ok(Repeat { frequency, iteration, index: 0 });
+        let found: Vec<_> = repeats(&[1 << 29, -(1 << 30)]).unwrap().collect();
+        assert_eq!(
+            vec![repeat(0, 1), repeat(-(1 << 29), 2), repeat(-(1 << 30), 3), repeat(-3 << 29, 4), repeat(i32::MIN, 5), Err(Overflow { step: 13 })],
+            found
+        );
+
+        // Climbs back to zero one step per pass, after over two billion changes.
+        assert_eq!(Ok(Some(Repeat { frequency: 0, iteration: 1 << 30, index: 0 })), find_first_repeat(&[-(1 << 30), (1 << 30) + 1]));
+    }
+
+    fn simulate(changes: &[i32], max_steps: usize, max_repeats: usize) -> Vec<Repeat<i32>> {
+        let mut seen = HashMap::new();
+        let mut repeats = Vec::new();
+        let mut frequency = 0;
+        seen.insert(0, 1);
+
+        for step in 0..max_steps {
+            frequency += changes[step % changes.len()];
+            let count = seen.entry(frequency).or_insert(0);
+            *count += 1;
+
+            if *count == 2 {
+                repeats.push(Repeat { frequency, iteration: step / changes.len(), index: step % changes.len() });
+                if repeats.len() == max_repeats {
+                    break;
+                }
+            }
+        }
+
+        repeats
+    }
+
+    #[test]
+    fn should_match_simulation() {
+        let mut rng = helpers::test_rng(7);
+        let mut next = |bound: i32| rng.below(bound as u64) as i32;
+
+        for _ in 0..300 {
+            let len = 1 + next(12) as usize;
+            let changes: Vec<i32> = (0..len).map(|_| next(41) - 20).collect();
+            let wide: Vec<i128> = changes.iter().map(|&c| i128::from(c)).collect();
+            let expected = simulate(&changes, 20_000, 20);
+
+            assert_eq!(Ok(expected.first().cloned()), find_first_repeat(&changes), "changes {:?}", changes);
+            assert_eq!(expected, repeats(&changes).unwrap().take(20).filter_map(Result::ok).collect::<Vec<_>>(), "changes {:?}", changes);
+            assert_eq!(
+                expected.iter().map(|r| i128::from(r.frequency)).collect::<Vec<_>>(),
+                repeats(&wide).unwrap().take(20).filter_map(Result::ok).map(|r| r.frequency).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn should_analyse_changes() {
+        let analysis = analyse(&[3, 3, 4, -2, -4], 3).unwrap();
+
+        assert_eq!(vec![0, 3, 6, 10, 8, 4], analysis.series);
+        assert_eq!((0, 10), (analysis.min, analysis.max));
+        assert_eq!(4, analysis.drift);
+        assert_eq!(vec![10, 8, 14], analysis.repeats.iter().map(|r| r.frequency).collect::<Vec<_>>());
+        assert_eq!(None, analysis.overflow);
+        assert_eq!(vec![(-4, 1), (-2, 1), (3, 2), (4, 1)], analysis.histogram);
+
+        let analysis = analyse(&[1i32, 2], 5).unwrap();
+        assert!(analysis.repeats.is_empty());
+        assert_eq!(None, analysis.overflow);
+        assert_eq!(Some(Overflow { step: 13 }), analyse(&[1 << 29, -(1 << 30)], 10).unwrap().overflow);
+        assert_eq!(vec![0], analyse::<i64>(&[], 5).unwrap().series);
+        assert!(analyse(&[i32::MAX, 1], 5).is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn should_track_big_frequencies() {
+        use num_bigint::BigInt;
+
+        let changes: Vec<BigInt> = parse_changes("+170141183460469231731687303715884105727\n+1\n".as_bytes()).unwrap();
+        assert_eq!(Ok(BigInt::from(i128::MAX) + 1), sum_changes(&changes));
+        assert_eq!(Ok(None), find_first_repeat(&changes));
+
+        let changes: Vec<BigInt> = [1, -2, 3, 1].iter().map(|&c| BigInt::from(c)).collect();
+        assert_eq!(Some(BigInt::from(2)), find_first_repeat(&changes).unwrap().map(|r| r.frequency));
     }
 }
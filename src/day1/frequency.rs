@@ -0,0 +1,162 @@
+//! Numeric types the day 1 frequency engine can track frequencies in.
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::hash::Hash;
+use std::str::FromStr;
+
+/// A frequency left the range of its numeric type after `step` changes.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Overflow {
+    pub step: u64,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Frequency overflowed after {} changes", self.step)
+    }
+}
+
+impl error::Error for Overflow {}
+
+/// A signed integer type for frequencies. Every operation is checked, returning `None` rather
+/// than wrapping or panicking when the result does not fit.
+pub trait Frequency: Clone + Ord + Hash + fmt::Debug + fmt::Display + FromStr {
+    fn zero() -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn checked_rem_euclid(&self, other: &Self) -> Option<Self>;
+    fn checked_mul_u64(&self, factor: u64) -> Option<Self>;
+    fn to_u64(&self) -> Option<u64>;
+    /// How many whole `drift`s lie between `self` and `other`, if that fits in a `u64`. The
+    /// distance is measured exactly, even where `other - self` does not fit in the type.
+    fn passes(&self, other: &Self, drift: &Self) -> Option<u64>;
+}
+
+macro_rules! impl_frequency {
+    ($($t:ty => $u:ty),*) => {$(
+        impl Frequency for $t {
+            fn zero() -> $t { 0 }
+
+            fn checked_add(&self, other: &$t) -> Option<$t> {
+                <$t>::checked_add(*self, *other)
+            }
+
+            fn checked_sub(&self, other: &$t) -> Option<$t> {
+                <$t>::checked_sub(*self, *other)
+            }
+
+            fn checked_div(&self, other: &$t) -> Option<$t> {
+                <$t>::checked_div(*self, *other)
+            }
+
+            fn checked_rem_euclid(&self, other: &$t) -> Option<$t> {
+                <$t>::checked_rem_euclid(*self, *other)
+            }
+
+            fn checked_mul_u64(&self, factor: u64) -> Option<$t> {
+                <$t>::try_from(factor).ok().and_then(|factor| <$t>::checked_mul(*self, factor))
+            }
+
+            fn to_u64(&self) -> Option<u64> {
+                u64::try_from(*self).ok()
+            }
+
+            fn passes(&self, other: &$t, drift: &$t) -> Option<u64> {
+                // The wrapped difference of the larger minus the smaller is exact when unsigned.
+                let (high, low) = if self < other { (*other, *self) } else { (*self, *other) };
+                let distance = high.wrapping_sub(low) as $u;
+                let step = drift.wrapping_abs() as $u;
+
+                distance.checked_div(step).and_then(|passes| u64::try_from(passes).ok())
+            }
+        }
+    )*};
+}
+
+impl_frequency!(i32 => u32, i64 => u64, i128 => u128);
+
+#[cfg(feature = "bigint")]
+mod bigint {
+    use num_bigint::BigInt;
+    use num_traits::{Signed, ToPrimitive, Zero};
+
+    use super::Frequency;
+
+    impl Frequency for BigInt {
+        fn zero() -> BigInt { Zero::zero() }
+
+        fn checked_add(&self, other: &BigInt) -> Option<BigInt> {
+            Some(self + other)
+        }
+
+        fn checked_sub(&self, other: &BigInt) -> Option<BigInt> {
+            Some(self - other)
+        }
+
+        fn checked_div(&self, other: &BigInt) -> Option<BigInt> {
+            if other.is_zero() { None } else { Some(self / other) }
+        }
+
+        fn checked_rem_euclid(&self, other: &BigInt) -> Option<BigInt> {
+            if other.is_zero() {
+                return None;
+            }
+
+            let rem = self % other;
+            Some(if rem.is_negative() { rem + other.abs() } else { rem })
+        }
+
+        fn checked_mul_u64(&self, factor: u64) -> Option<BigInt> {
+            Some(self * BigInt::from(factor))
+        }
+
+        fn to_u64(&self) -> Option<u64> {
+            ToPrimitive::to_u64(self)
+        }
+
+        fn passes(&self, other: &BigInt, drift: &BigInt) -> Option<u64> {
+            if drift.is_zero() {
+                return None;
+            }
+
+            ToPrimitive::to_u64(&((other - self) / drift).abs())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_check_arithmetic() {
+        assert_eq!(None, Frequency::checked_add(&i32::MAX, &1));
+        assert_eq!(Some(i64::from(i32::MAX) + 1), Frequency::checked_add(&i64::from(i32::MAX), &1));
+        assert_eq!(Some(2), Frequency::checked_rem_euclid(&-7, &3));
+        assert_eq!(Some(2), Frequency::checked_rem_euclid(&-7, &-3));
+        assert_eq!(None, Frequency::checked_rem_euclid(&i32::MIN, &-1));
+        assert_eq!(None, Frequency::checked_mul_u64(&1i32, 1 << 40));
+        assert_eq!(Some(3 << 40), Frequency::checked_mul_u64(&3i128, 1 << 40));
+        assert_eq!(None, Frequency::to_u64(&-1i64));
+        assert_eq!(Some(3), Frequency::passes(&1, &7, &2));
+        assert_eq!(Some(3), Frequency::passes(&7, &1, &-2));
+        assert_eq!(Some(u64::from(u32::MAX)), Frequency::passes(&i32::MIN, &i32::MAX, &1));
+        assert_eq!(Some(1), Frequency::passes(&i32::MAX, &-1, &i32::MIN));
+        assert_eq!(None, Frequency::passes(&i128::MIN, &i128::MAX, &1));
+        assert_eq!(None, Frequency::passes(&0, &3, &0));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn should_not_overflow_big_integers() {
+        use num_bigint::BigInt;
+
+        let max = BigInt::from(i128::MAX);
+        assert_eq!(Some(BigInt::from(i128::MAX) * 2), Frequency::checked_add(&max, &max));
+        assert_eq!(Some(BigInt::from(2)), Frequency::checked_rem_euclid(&BigInt::from(-7), &BigInt::from(-3)));
+        assert_eq!(None, Frequency::checked_div(&max, &BigInt::from(0)));
+    }
+}
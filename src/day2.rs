@@ -1,57 +1,129 @@
-use std::collections::HashMap;
-use itertools::Itertools;
+//! Day 2: Inventory Management System.
 
-fn contains_duplicates(s: &str, n: i32) -> bool {
-    let mut chars: HashMap<char, i32> = HashMap::new();
+use std::collections::BTreeMap;
+use std::fmt;
 
-    s.chars().for_each(
-        |c| *chars.entry(c).or_insert(0) += 1
-    );
+use error::{ParseError, SolveError};
+use helpers;
+use solution::{Answer, Solution};
 
-    chars.values()
-        .find(|v| **v == n)
-        .is_some()
-}
+pub mod bktree;
+pub mod cluster;
+pub mod index;
+
+pub use self::bktree::{BkTree, Metric};
+pub use self::cluster::{cluster, Cluster, Clustering};
+pub use self::index::{NearMatch, SimilarityIndex};
 
-fn count_with_duplicates(strings: &Vec<String>, n: i32) -> usize {
-    strings.iter().filter(|s| contains_duplicates(s, n)).count()
+/// How many times each letter appears in an ID, in letter order.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Signature {
+    pub counts: Vec<(char, usize)>,
 }
 
-pub fn check_sum(strings: &Vec<String>) -> usize {
-    count_with_duplicates(strings, 2) * count_with_duplicates(strings, 3)
+impl Signature {
+    pub fn of(id: &str) -> Signature {
+        let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+        for c in id.chars() {
+            *counts.entry(c).or_insert(0) += 1;
+        }
+
+        Signature { counts: counts.into_iter().collect() }
+    }
+
+    /// Whether some letter appears exactly `n` times.
+    pub fn has_multiplicity(&self, n: usize) -> bool {
+        self.counts.iter().any(|&(_, count)| count == n)
+    }
 }
 
-fn compare_strings(a: &str, b: &str) -> String {
-    let a_s: Vec<char> = a.chars().collect();
-    let b_s: Vec<char> = b.chars().collect();
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts: Vec<String> = self.counts.iter().map(|(c, count)| format!("{}{}", c, count)).collect();
+        write!(f, "{}", counts.join(" "))
+    }
+}
 
-    let mut out: String = "".to_string();
+/// The letter-count signature of every ID, in input order.
+pub fn signature_report<S: AsRef<str>>(ids: &[S]) -> Vec<(&str, Signature)> {
+    ids.iter().map(|id| (id.as_ref(), Signature::of(id.as_ref()))).collect()
+}
 
-    a_s.iter().enumerate().for_each(
-        |(i, c)| {
-            if b_s[i] == *c { out.push(*c) }
+/// For each of `multiplicities`, in order, the number of IDs with a letter appearing exactly
+/// that many times. Each ID is counted once, however many multiplicities are asked for.
+pub fn multiplicity_counts<S: AsRef<str>>(ids: &[S], multiplicities: &[usize]) -> Vec<usize> {
+    let mut totals = vec![0; multiplicities.len()];
+
+    for id in ids {
+        let signature = Signature::of(id.as_ref());
+        for (total, &n) in totals.iter_mut().zip(multiplicities) {
+            if signature.has_multiplicity(n) {
+                *total += 1;
+            }
         }
-    );
+    }
 
-    out.to_string()
+    totals
 }
 
-pub fn find_matching(strings: &Vec<String>) -> Option<String> {
-    for (a, b) in strings.iter().tuple_combinations() {
-        let intersect = compare_strings(a, b);
-        if intersect.len() == a.len() - 1 {
-            return Some(intersect)
-        }
+/// Combines the `multiplicity_counts` for `multiplicities` into a checksum with `combine`.
+pub fn check_sum_with<S, F>(ids: &[S], multiplicities: &[usize], combine: F) -> usize
+    where S: AsRef<str>, F: Fn(&[usize]) -> usize
+{
+    combine(&multiplicity_counts(ids, multiplicities))
+}
+
+/// Multiplies the number of IDs containing a letter exactly twice by those containing one exactly three times.
+pub fn check_sum(strings: &[String]) -> usize {
+    check_sum_with(strings, &[2, 3], |counts| counts.iter().product())
+}
+
+/// The characters two IDs share at the same positions. Characters beyond the end of the shorter
+/// ID are never shared.
+fn compare_strings(a: &str, b: &str) -> String {
+    a.chars()
+        .zip(b.chars())
+        .filter(|(x, y)| x == y)
+        .map(|(c, _)| c)
+        .collect()
+}
+
+/// Finds the first pair of IDs that differ by one character and returns the letters they share.
+pub fn find_matching(strings: &[String]) -> Option<String> {
+    SimilarityIndex::new(strings).near_matches().first().map(|m| m.common())
+}
+
+/// Registry entry for day 2.
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<String>;
+
+    fn day(&self) -> u8 { 2 }
+
+    fn parse(&self, input: &str) -> Result<Vec<String>, ParseError> {
+        helpers::lines(input.as_bytes()).skip_blank().parse().collect()
+    }
+
+    fn part_1(&self, ids: &Vec<String>) -> Result<Answer, SolveError> {
+        Ok(Answer::new("Checksum", check_sum(ids)))
     }
 
-    return None;
+    fn part_2(&self, ids: &Vec<String>) -> Result<Answer, SolveError> {
+        Ok(Answer::new("Common letters", find_matching(ids).unwrap_or_else(|| "none".to_string())))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn contains_duplicates(s: &str, n: usize) -> bool {
+        Signature::of(s).has_multiplicity(n)
+    }
+
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn should_check_for_duplicates() {
         assert_eq!(contains_duplicates("abcdef", 2), false);
         assert_eq!(contains_duplicates("abcdef", 3), false);
@@ -75,21 +147,43 @@ mod tests {
         assert_eq!(contains_duplicates("ababab", 3), true);
     }
 
+    fn example_ids() -> Vec<String> {
+        ["abcdef",
+         "bababc",
+         "abbcde",
+         "abcccd",
+         "aabcdd",
+         "abcdee",
+         "ababab"].iter().map(|x| x.to_string()).collect()
+    }
+
     #[test]
     fn should_get_correct_checksum() {
-        let strings = vec!["abcdef",
-                           "bababc",
-                           "abbcde",
-                           "abcccd",
-                           "aabcdd",
-                           "abcdee",
-                           "ababab"].iter().map(|x| x.to_string()).collect();
-
-        assert_eq!(count_with_duplicates(&strings, 2), 4);
-        assert_eq!(count_with_duplicates(&strings, 3), 3);
+        let strings = example_ids();
+
+        assert_eq!(multiplicity_counts(&strings, &[2, 3]), vec![4, 3]);
         assert_eq!(check_sum(&strings), 12);
     }
 
+    #[test]
+    fn should_combine_any_multiplicities() {
+        let strings = example_ids();
+
+        assert_eq!(vec![6, 4, 3, 0], multiplicity_counts(&strings, &[1, 2, 3, 4]));
+        assert_eq!(13, check_sum_with(&strings, &[1, 2, 3], |counts| counts.iter().sum()));
+        assert_eq!(0, check_sum_with(&strings, &[2, 4], |counts| counts.iter().product()));
+        assert_eq!(1, check_sum_with(&strings, &[], |counts| counts.iter().product()));
+    }
+
+    #[test]
+    fn should_report_signatures() {
+        let report = signature_report(&["bababc", "abcccd"]);
+
+        assert_eq!("bababc", report[0].0);
+        assert_eq!(Signature { counts: vec![('a', 2), ('b', 3), ('c', 1)] }, report[0].1);
+        assert_eq!("a1 b1 c3 d1", report[1].1.to_string());
+    }
+
     #[test]
     fn can_compare_strings() {
         assert_eq!("", compare_strings("abcde", "fghij"));
@@ -97,11 +191,13 @@ mod tests {
         assert_eq!("ace", compare_strings("abcde", "axcye"));
         assert_eq!("abcde", compare_strings("abcde", "abcde"));
         assert_eq!("a", compare_strings("accbb", "abbcc"));
+        assert_eq!("ab", compare_strings("abcde", "ab"));
+        assert_eq!("ab", compare_strings("ab", "abcde"));
     }
 
     #[test]
     fn finds_matching_string() {
-        let strings = vec!["abcde",
+        let strings: Vec<String> = ["abcde",
                            "fghij",
                            "klmno",
                            "pqrst",
@@ -0,0 +1,195 @@
+//! Searching box IDs for everything within an edit distance of a query, using a BK-tree.
+//!
+//! Each node keeps its children keyed by their distance from it. As the metric obeys the
+//! triangle inequality, a query within `k` of the target only needs to visit children whose key
+//! is within `k` of the node's own distance from the target.
+
+use std::collections::HashMap;
+
+/// How far apart two IDs are.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Metric {
+    /// Positions where the IDs differ. The shorter ID is treated as if padded with characters
+    /// matching nothing, so each extra character counts as one difference.
+    Hamming,
+    /// Single character insertions, deletions and substitutions needed to turn one into the other.
+    Levenshtein,
+}
+
+impl Metric {
+    pub fn distance(self, a: &str, b: &str) -> usize {
+        match self {
+            Metric::Hamming => hamming(a, b),
+            Metric::Levenshtein => levenshtein(a, b),
+        }
+    }
+}
+
+fn hamming(a: &str, b: &str) -> usize {
+    let (a_len, b_len) = (a.chars().count(), b.chars().count());
+    let mismatched = a.chars().zip(b.chars()).filter(|(x, y)| x != y).count();
+
+    mismatched + a_len.max(b_len) - a_len.min(b_len)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, x) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &y) in b.iter().enumerate() {
+            let substitution = previous[j] + if x == y { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        ::std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+struct Node<'a> {
+    id: &'a str,
+    /// Child node indices keyed by their distance from this node.
+    children: HashMap<usize, usize>,
+}
+
+/// Box IDs indexed for near-match queries under a chosen metric.
+pub struct BkTree<'a> {
+    metric: Metric,
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> BkTree<'a> {
+    pub fn new(metric: Metric) -> BkTree<'a> {
+        BkTree { metric, nodes: Vec::new() }
+    }
+
+    /// Indexes every ID in `ids`.
+    pub fn build<S: AsRef<str>>(ids: &'a [S], metric: Metric) -> BkTree<'a> {
+        let mut tree = BkTree::new(metric);
+        for id in ids {
+            tree.insert(id.as_ref());
+        }
+        tree
+    }
+
+    /// The number of distinct IDs indexed.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds `id` to the tree. IDs already present are ignored.
+    pub fn insert(&mut self, id: &'a str) {
+        let new = self.nodes.len();
+        let mut current = 0;
+
+        while current < self.nodes.len() {
+            let distance = self.metric.distance(self.nodes[current].id, id);
+            if distance == 0 {
+                return;
+            }
+
+            match self.nodes[current].children.get(&distance) {
+                Some(&child) => current = child,
+                None => {
+                    self.nodes[current].children.insert(distance, new);
+                    break;
+                }
+            }
+        }
+
+        self.nodes.push(Node { id, children: HashMap::new() });
+    }
+
+    /// Every indexed ID within `k` of `id`, with its distance, nearest first then in ID order.
+    pub fn query(&self, id: &str, k: usize) -> Vec<(&'a str, usize)> {
+        let mut found = Vec::new();
+        let mut pending = if self.nodes.is_empty() { vec![] } else { vec![0] };
+
+        while let Some(index) = pending.pop() {
+            let node = &self.nodes[index];
+            let distance = self.metric.distance(node.id, id);
+
+            if distance <= k {
+                found.push((node.id, distance));
+            }
+
+            pending.extend(
+                node.children
+                    .iter()
+                    .filter(|&(&d, _)| d + k >= distance && d <= distance + k)
+                    .map(|(_, &child)| child)
+            );
+        }
+
+        found.sort_by_key(|&(id, distance)| (distance, id));
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helpers;
+
+    #[test]
+    fn should_measure_distance() {
+        assert_eq!(1, Metric::Hamming.distance("fghij", "fguij"));
+        assert_eq!(3, Metric::Hamming.distance("abc", ""));
+        assert_eq!(2, Metric::Hamming.distance("abcd", "abx"));
+        assert_eq!(1, Metric::Hamming.distance("é", "e"));
+
+        assert_eq!(3, Metric::Levenshtein.distance("kitten", "sitting"));
+        assert_eq!(1, Metric::Levenshtein.distance("abcd", "abd"));
+        assert_eq!(4, Metric::Levenshtein.distance("", "abcd"));
+        assert_eq!(0, Metric::Levenshtein.distance("abc", "abc"));
+    }
+
+    #[test]
+    fn should_query_ids() {
+        let ids = ["abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz", "fghij", "fghi"];
+        let tree = BkTree::build(&ids, Metric::Hamming);
+
+        assert_eq!(8, tree.len());
+        assert_eq!(vec![("fghij", 0), ("fghi", 1), ("fguij", 1)], tree.query("fghij", 1));
+        assert_eq!(vec![("abcde", 1), ("axcye", 1)], tree.query("axcde", 1));
+
+        let tree = BkTree::build(&ids, Metric::Levenshtein);
+        assert_eq!(vec![("fghi", 1), ("fghij", 2)], tree.query("fghxi", 2));
+        assert!(BkTree::build(&[] as &[&str], Metric::Levenshtein).query("abc", 3).is_empty());
+    }
+
+    #[test]
+    fn should_match_brute_force() {
+        let mut rng = helpers::test_rng(5);
+        let mut next = |bound: u64| rng.below(bound);
+
+        let ids: Vec<String> = (0..400)
+            .map(|_| (0..3 + next(4)).map(|_| (b'a' + next(4) as u8) as char).collect())
+            .collect();
+
+        for &metric in [Metric::Hamming, Metric::Levenshtein].iter() {
+            let tree = BkTree::build(&ids, metric);
+
+            for query in ids.iter().take(40) {
+                for k in 0..3 {
+                    let mut expected: Vec<(&str, usize)> = ids
+                        .iter()
+                        .map(|id| (id.as_str(), metric.distance(id, query)))
+                        .filter(|&(_, d)| d <= k)
+                        .collect();
+                    expected.sort_by_key(|&(id, d)| (d, id));
+                    expected.dedup();
+
+                    assert_eq!(expected, tree.query(query, k), "{:?} within {} of {}", metric, k, query);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Grouping box IDs into clusters connected by chains of single character differences.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use output::json_string;
+
+use super::index::{NearMatch, SimilarityIndex};
+
+/// Disjoint sets of indices, merged by size with path halving.
+struct DisjointSets {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> DisjointSets {
+        DisjointSets { parent: (0..len).collect(), size: vec![1; len] }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+
+        let (big, small) = if self.size[a] >= self.size[b] { (a, b) } else { (b, a) };
+        self.parent[small] = big;
+        self.size[big] += self.size[small];
+    }
+}
+
+/// A group of IDs, in input order. The representative is the first member.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Cluster<'a> {
+    pub members: Vec<&'a str>,
+    /// The characters at positions where every member agrees.
+    pub common: String,
+}
+
+impl<'a> Cluster<'a> {
+    pub fn size(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn representative(&self) -> &'a str {
+        self.members[0]
+    }
+}
+
+fn common_letters(members: &[&str]) -> String {
+    if members.is_empty() {
+        return String::new();
+    }
+
+    let mut chars: Vec<_> = members.iter().map(|m| m.chars()).collect();
+    let mut common = String::new();
+
+    loop {
+        let column: Option<Vec<char>> = chars.iter_mut().map(|c| c.next()).collect();
+        match column {
+            Some(ref column) if column.iter().all(|&c| c == column[0]) => common.push(column[0]),
+            Some(_) => continue,
+            None => return common,
+        }
+    }
+}
+
+/// Box IDs split into clusters, along with the single character differences linking them.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Clustering<'a> {
+    /// Largest first, then by the position of their representative.
+    pub clusters: Vec<Cluster<'a>>,
+    pub links: Vec<NearMatch<'a>>,
+}
+
+/// Clusters IDs connected by chains of IDs differing in one position. Repeated IDs share a
+/// cluster.
+pub fn cluster<'a, S: AsRef<str>>(ids: &'a [S]) -> Clustering<'a> {
+    let links = SimilarityIndex::new(ids).near_matches();
+    let mut sets = DisjointSets::new(ids.len());
+
+    for link in links.iter() {
+        sets.union(link.indices.0, link.indices.1);
+    }
+
+    let mut first_seen = HashMap::new();
+    for (i, id) in ids.iter().enumerate() {
+        let first = *first_seen.entry(id.as_ref()).or_insert(i);
+        sets.union(first, i);
+    }
+
+    // Groups are numbered as their first member is reached, so they stay in representative order.
+    let mut group_of_root = HashMap::new();
+    let mut groups: Vec<Vec<&str>> = Vec::new();
+    for (i, id) in ids.iter().enumerate() {
+        let root = sets.find(i);
+        let group = *group_of_root.entry(root).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(id.as_ref());
+    }
+
+    let mut clusters: Vec<Cluster> = groups
+        .into_iter()
+        .map(|members| Cluster { common: common_letters(&members), members })
+        .collect();
+
+    clusters.sort_by_key(|c| Reverse(c.size()));
+
+    Clustering { clusters, links }
+}
+
+/// Renders clusters of at least `min_size` IDs as a JSON array.
+pub fn to_json(clustering: &Clustering, min_size: usize) -> String {
+    let clusters: Vec<String> = clustering.clusters
+        .iter()
+        .filter(|c| c.size() >= min_size)
+        .map(|c| {
+            let members: Vec<String> = c.members.iter().map(|m| json_string(m)).collect();
+            format!(
+                "{{\"size\":{},\"representative\":{},\"common\":{},\"members\":[{}]}}",
+                c.size(), json_string(c.representative()), json_string(&c.common), members.join(",")
+            )
+        })
+        .collect();
+
+    format!("[{}]\n", clusters.join(",\n"))
+}
+
+/// Renders clusters of at least `min_size` IDs as a Graphviz graph, one subgraph per cluster
+/// labelled with its common letters, and an edge per link labelled with the differing position.
+pub fn to_dot(clustering: &Clustering, min_size: usize) -> String {
+    let mut out = String::from("graph clusters {\n");
+    let mut shown = HashSet::new();
+
+    for (i, c) in clustering.clusters.iter().filter(|c| c.size() >= min_size).enumerate() {
+        out.push_str(&format!("    subgraph cluster_{} {{\n        label={};\n", i, json_string(&c.common)));
+        for member in c.members.iter() {
+            if shown.insert(*member) {
+                out.push_str(&format!("        {};\n", json_string(member)));
+            }
+        }
+        out.push_str("    }\n");
+    }
+
+    for link in clustering.links.iter().filter(|l| shown.contains(l.ids.0)) {
+        out.push_str(&format!(
+            "    {} -- {} [label={}];\n",
+            json_string(link.ids.0), json_string(link.ids.1), link.position
+        ));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDS: [&str; 9] = ["abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "fguik", "klmno", "fghix"];
+
+    #[test]
+    fn should_cluster_ids() {
+        let clustering = cluster(&IDS);
+        let summary: Vec<(Vec<&str>, &str)> = clustering.clusters.iter().map(|c| (c.members.clone(), c.common.as_str())).collect();
+
+        assert_eq!(
+            vec![
+                (vec!["fghij", "fguij", "fguik", "fghix"], "fgi"),
+                (vec!["klmno", "klmno"], "klmno"),
+                (vec!["abcde"], "abcde"),
+                (vec!["pqrst"], "pqrst"),
+                (vec!["axcye"], "axcye"),
+            ],
+            summary
+        );
+        assert_eq!("fghij", clustering.clusters[0].representative());
+        assert_eq!(3, clustering.links.len());
+    }
+
+    #[test]
+    fn should_order_equal_clusters_by_representative() {
+        let ids = ["aaaa", "cccc", "cccd", "abbb", "aabb", "aaab", "ccdd", "cddd"];
+        let representatives: Vec<&str> = cluster(&ids).clusters.iter().map(|c| c.representative()).collect();
+
+        assert_eq!(vec!["aaaa", "cccc"], representatives);
+    }
+
+    #[test]
+    fn should_find_common_letters() {
+        assert_eq!("fgij", common_letters(&["fghij", "fguij"]));
+        assert_eq!("ab", common_letters(&["abc", "ab", "abd"]));
+        assert_eq!("", common_letters(&[]));
+    }
+
+    #[test]
+    fn should_export_json() {
+        assert_eq!(
+            "[{\"size\":4,\"representative\":\"fghij\",\"common\":\"fgi\",\"members\":[\"fghij\",\"fguij\",\"fguik\",\"fghix\"]},\n\
+            {\"size\":2,\"representative\":\"klmno\",\"common\":\"klmno\",\"members\":[\"klmno\",\"klmno\"]}]\n",
+            to_json(&cluster(&IDS), 2)
+        );
+    }
+
+    #[test]
+    fn should_export_dot() {
+        assert_eq!(
+            "graph clusters {\n    \
+                subgraph cluster_0 {\n        label=\"fgi\";\n        \"fghij\";\n        \"fguij\";\n        \"fguik\";\n        \"fghix\";\n    }\n    \
+                \"fghij\" -- \"fguij\" [label=2];\n    \
+                \"fghij\" -- \"fghix\" [label=4];\n    \
+                \"fguij\" -- \"fguik\" [label=4];\n\
+            }\n",
+            to_dot(&cluster(&IDS), 3)
+        );
+    }
+}
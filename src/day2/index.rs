@@ -0,0 +1,188 @@
+//! Finding every pair of box IDs that differ in exactly one position.
+//!
+//! For each position, IDs are bucketed by the text either side of it. Two IDs of the same length
+//! differ only at that position exactly when they share a bucket, so every such pair is found
+//! with one hash lookup per ID and position instead of comparing every pair of IDs.
+
+use std::collections::HashMap;
+
+use super::compare_strings;
+
+/// Two IDs that differ only at `position`, counted in characters. `indices` locate the IDs in
+/// the indexed list, with the first before the second.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct NearMatch<'a> {
+    pub ids: (&'a str, &'a str),
+    pub indices: (usize, usize),
+    pub position: usize,
+}
+
+impl<'a> NearMatch<'a> {
+    /// The characters both IDs share.
+    pub fn common(&self) -> String {
+        compare_strings(self.ids.0, self.ids.1)
+    }
+}
+
+/// Box IDs prepared for similarity searches. Repeated IDs are indexed once, so they cost nothing
+/// beyond the matches they take part in.
+pub struct SimilarityIndex<'a> {
+    /// Each distinct ID, in order of first appearance.
+    ids: Vec<&'a str>,
+    /// The indices at which each distinct ID appears.
+    occurrences: Vec<Vec<usize>>,
+    /// The byte offset of each character in each distinct ID, followed by the ID's length.
+    boundaries: Vec<Vec<usize>>,
+}
+
+impl<'a> SimilarityIndex<'a> {
+    pub fn new<S: AsRef<str>>(ids: &'a [S]) -> SimilarityIndex<'a> {
+        let mut distinct: HashMap<&str, usize> = HashMap::new();
+        let mut occurrences: Vec<Vec<usize>> = Vec::new();
+        for (index, id) in ids.iter().enumerate() {
+            let slot = *distinct.entry(id.as_ref()).or_insert_with(|| {
+                occurrences.push(Vec::new());
+                occurrences.len() - 1
+            });
+            occurrences[slot].push(index);
+        }
+
+        let ids: Vec<&str> = occurrences.iter().map(|o| ids[o[0]].as_ref()).collect();
+        let boundaries = ids
+            .iter()
+            .map(|id| id.char_indices().map(|(i, _)| i).chain(Some(id.len())).collect())
+            .collect();
+
+        SimilarityIndex { ids, occurrences, boundaries }
+    }
+
+    /// Every pair of IDs differing in exactly one position, ordered by their indices. Identical
+    /// IDs are not included.
+    pub fn near_matches(&self) -> Vec<NearMatch<'a>> {
+        let longest = self.boundaries.iter().map(|b| b.len() - 1).max().unwrap_or(0);
+        let mut buckets: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+        let mut matches = Vec::new();
+
+        for position in 0..longest {
+            buckets.clear();
+
+            for (slot, (id, bounds)) in self.ids.iter().zip(self.boundaries.iter()).enumerate() {
+                if position + 1 < bounds.len() {
+                    let key = (&id[..bounds[position]], &id[bounds[position + 1]..]);
+                    buckets.entry(key).or_default().push(slot);
+                }
+            }
+
+            for bucket in buckets.values().filter(|b| b.len() > 1) {
+                for (i, &a) in bucket.iter().enumerate() {
+                    for &b in bucket[i + 1..].iter() {
+                        self.push_matches(a, b, position, &mut matches);
+                    }
+                }
+            }
+        }
+
+        matches.sort_by_key(|m| (m.indices, m.position));
+        matches
+    }
+
+    /// Adds a match for every pair of occurrences of the distinct IDs `a` and `b`.
+    fn push_matches(&self, a: usize, b: usize, position: usize, matches: &mut Vec<NearMatch<'a>>) {
+        for &i in self.occurrences[a].iter() {
+            for &j in self.occurrences[b].iter() {
+                let (first, second) = if i < j { (a, b) } else { (b, a) };
+                matches.push(NearMatch { ids: (self.ids[first], self.ids[second]), indices: (i.min(j), i.max(j)), position });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helpers;
+
+    fn distance(a: &str, b: &str) -> Option<usize> {
+        if a.chars().count() != b.chars().count() {
+            return None;
+        }
+        Some(a.chars().zip(b.chars()).filter(|(x, y)| x != y).count())
+    }
+
+    #[test]
+    fn should_find_near_matches() {
+        let ids = ["abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz", "fghix", "fghij"];
+        let matches = SimilarityIndex::new(&ids).near_matches();
+
+        assert_eq!(
+            vec![
+                NearMatch { ids: ("fghij", "fguij"), indices: (1, 4), position: 2 },
+                NearMatch { ids: ("fghij", "fghix"), indices: (1, 7), position: 4 },
+                NearMatch { ids: ("fguij", "fghij"), indices: (4, 8), position: 2 },
+                NearMatch { ids: ("fghix", "fghij"), indices: (7, 8), position: 4 },
+            ],
+            matches
+        );
+        assert_eq!("fgij", matches[0].common());
+    }
+
+    #[test]
+    fn should_handle_mixed_lengths_and_unicode() {
+        let ids = ["ab", "abc", "b", "", "é", "e", "aé", "ae"];
+        let found: Vec<_> = SimilarityIndex::new(&ids).near_matches().iter().map(|m| (m.ids, m.position)).collect();
+
+        assert_eq!(
+            vec![(("ab", "aé"), 1), (("ab", "ae"), 1), (("b", "é"), 0), (("b", "e"), 0), (("é", "e"), 0), (("aé", "ae"), 1)],
+            found
+        );
+    }
+
+    #[test]
+    fn should_match_brute_force() {
+        let mut rng = helpers::test_rng(11);
+        let mut next = |bound: u64| rng.below(bound);
+
+        let ids: Vec<String> = (0..300)
+            .map(|_| (0..2 + next(3)).map(|_| (b'a' + next(3) as u8) as char).collect())
+            .collect();
+
+        let mut expected = Vec::new();
+        for i in 0..ids.len() {
+            for j in i + 1..ids.len() {
+                if distance(&ids[i], &ids[j]) == Some(1) {
+                    expected.push((i, j));
+                }
+            }
+        }
+
+        let found: Vec<_> = SimilarityIndex::new(&ids).near_matches().iter().map(|m| m.indices).collect();
+        assert_eq!(expected, found);
+    }
+
+    #[test]
+    fn should_scale_to_many_ids() {
+        let mut rng = helpers::test_rng(3);
+        let mut ids: Vec<String> = (0..100_000).map(|_| rng.string(26, b"abcdefghijklmnopqrstuvwxyz")).collect();
+
+        let mut twin: Vec<char> = ids[1234].chars().collect();
+        twin[7] = if twin[7] == 'z' { 'a' } else { 'z' };
+        ids.push(twin.into_iter().collect());
+
+        let matches = SimilarityIndex::new(&ids).near_matches();
+        assert_eq!(1, matches.len());
+        assert_eq!(((1234, 100_000), 7), (matches[0].indices, matches[0].position));
+    }
+
+    #[test]
+    fn should_skip_repeated_ids_quickly() {
+        let mut ids = vec!["abcd"; 50_000];
+        ids.push("abce");
+        ids.push("abcd");
+        let matches = SimilarityIndex::new(&ids).near_matches();
+
+        assert_eq!(50_001, matches.len());
+        assert_eq!(NearMatch { ids: ("abcd", "abce"), indices: (0, 50_000), position: 3 }, matches[0]);
+        assert_eq!(NearMatch { ids: ("abce", "abcd"), indices: (50_000, 50_001), position: 3 }, matches[50_000]);
+        assert!(SimilarityIndex::new(&vec!["abcd"; 50_000]).near_matches().is_empty());
+    }
+}
@@ -1,54 +1,96 @@
-use std::collections::BTreeSet;
+//! Day 3: No Matter How You Slice It.
+
+use std::cell::{Ref, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::Read;
+use std::str::FromStr;
 
 use regex::Regex;
 
+use error::{ParseError, SolveError};
+use helpers;
+use solution::{Answer, Solution};
+
+pub mod sweep;
+
+/// A rectangular claim on the fabric, `w` by `h` inches with its top left corner at `x`, `y`.
 #[derive(Debug, Eq, PartialEq)]
 pub struct Claim {
-    id: i32,
-    x: i32,
-    y: i32,
-    w: i32,
-    h: i32,
+    pub id: u32,
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Claim {
+    /// The column just past the claim's right edge.
+    pub fn right(&self) -> u64 {
+        u64::from(self.x) + u64::from(self.w)
+    }
+
+    /// The row just past the claim's bottom edge.
+    pub fn bottom(&self) -> u64 {
+        u64::from(self.y) + u64::from(self.h)
+    }
+
+    fn cells<'a>(&'a self, fabric: &'a Fabric) -> impl Iterator<Item=u128> + 'a {
+        (u64::from(self.y)..self.bottom())
+            .flat_map(move |y| (u64::from(self.x)..self.right()).map(move |x| fabric.position(x, y)))
+    }
+}
+
+/// The smallest fabric holding every claim, used to give each square inch a distinct position.
+struct Fabric {
+    width: u64,
+}
+
+impl Fabric {
+    fn new(claims: &[Claim]) -> Fabric {
+        Fabric { width: claims.iter().map(|c| c.right()).max().unwrap_or(0) }
+    }
+
+    /// Rows and columns can each reach `2 * u32::MAX`, so positions need 128 bits.
+    fn position(&self, x: u64, y: u64) -> u128 {
+        u128::from(y) * u128::from(self.width) + u128::from(x)
+    }
 }
 
-fn parse_claim(claim: &str) -> Option<Claim> {
+fn parse_claim(claim: &str) -> Result<Claim, String> {
     lazy_static! {
-        static ref CLAIM_MATCHER: Regex = Regex::new(r"#(\d+) @ (\d+),(\d+): (\d+)x(\d+)").unwrap();
-    }
-
-    CLAIM_MATCHER
-        .captures(claim)
-        .map(|matches|
-            Claim {
-                id: matches.get(1).unwrap().as_str().parse().unwrap(),
-                x: matches.get(2).unwrap().as_str().parse().unwrap(),
-                y: matches.get(3).unwrap().as_str().parse().unwrap(),
-                w: matches.get(4).unwrap().as_str().parse().unwrap(),
-                h: matches.get(5).unwrap().as_str().parse().unwrap(),
-            }
-        )
+        static ref CLAIM_MATCHER: Regex = Regex::new(r"^#(\d+) @ (\d+),(\d+): (\d+)x(\d+)$").unwrap();
+    }
+
+    let matches = CLAIM_MATCHER
+        .captures(claim.trim())
+        .ok_or_else(|| "expected a claim like '#1 @ 1,3: 4x4'".to_string())?;
+
+    let field = |i: usize| matches[i].parse::<u32>().map_err(|e| format!("{} '{}'", e, &matches[i]));
+
+    Ok(Claim { id: field(1)?, x: field(2)?, y: field(3)?, w: field(4)?, h: field(5)? })
 }
 
-pub fn parse_claims(claims: &Vec<String>) -> Vec<Claim> {
-    claims
-        .iter()
-        .map(|c| parse_claim(c.as_str()))
-        .flatten()
-        .collect()
+impl FromStr for Claim {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Claim, String> {
+        parse_claim(s)
+    }
 }
 
-fn get_conflicted(claims: &Vec<Claim>) -> BTreeSet<i32> {
+/// Parses one claim per line in the form `#1 @ 1,3: 4x4`, skipping blank lines.
+pub fn parse_claims<R: Read>(input: R) -> Result<Vec<Claim>, ParseError> {
+    helpers::lines(input).skip_blank().parse().collect()
+}
+
+fn get_conflicted(claims: &[Claim], fabric: &Fabric) -> BTreeSet<u128> {
     let mut covered = BTreeSet::new();
     let mut contested = BTreeSet::new();
 
     for claim in claims.iter() {
-        for x in claim.x..(claim.x + claim.w) {
-            for y in claim.y..(claim.y + claim.h) {
-                let pos = x + (y * 1000);
-
-                if !covered.insert(pos) {
-                    contested.insert(pos);
-                }
+        for pos in claim.cells(fabric) {
+            if !covered.insert(pos) {
+                contested.insert(pos);
             }
         }
     }
@@ -56,28 +98,153 @@ fn get_conflicted(claims: &Vec<Claim>) -> BTreeSet<i32> {
     contested
 }
 
-pub fn get_conflicted_area(claims: &Vec<Claim>) -> usize {
-    get_conflicted(claims).len()
+/// Counts the square inches covered by two or more claims.
+pub fn get_conflicted_area(claims: &[Claim]) -> usize {
+    get_conflicted(claims, &Fabric::new(claims)).len()
 }
 
-pub fn get_unique_claim_id(claims: &Vec<Claim>) -> Option<i32> {
-    let conflicted = get_conflicted(claims);
+/// Finds the first claim that overlaps no other claim.
+pub fn get_unique_claim_id(claims: &[Claim]) -> Option<u32> {
+    let fabric = Fabric::new(claims);
+    let conflicted = get_conflicted(claims, &fabric);
 
-    'claim_loop: for claim in claims.iter() {
-        for x in claim.x..(claim.x + claim.w) {
-            for y in claim.y..(claim.y + claim.h) {
-                let pos = x + (y * 1000);
+    claims
+        .iter()
+        .find(|claim| claim.cells(&fabric).all(|pos| !conflicted.contains(&pos)))
+        .map(|claim| claim.id)
+}
 
-                if conflicted.contains(&pos) {
-                    continue 'claim_loop;
-                }
-            }
+/// How claims are laid over the fabric to find overlaps.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Engine {
+    /// Tracks every square inch claimed. Simple, but costs time and memory per square inch.
+    Cells,
+    /// Sweeps over claim edges, costing time per claim whatever their size. See
+    /// [`sweep`](sweep/index.html).
+    #[default]
+    Sweep,
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Engine, String> {
+        match s {
+            "cells" => Ok(Engine::Cells),
+            "sweep" => Ok(Engine::Sweep),
+            _ => Err(format!("Unknown engine '{}', expected cells or sweep", s)),
+        }
+    }
+}
+
+/// How the claims cover the fabric.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Coverage {
+    /// The area covered by two or more claims.
+    pub contested_area: u128,
+    /// The area covered by exactly each number of claims, for every depth that occurs. Only
+    /// worked out by [`coverage_by_depth`](fn.coverage_by_depth.html).
+    pub area_by_depth: Option<BTreeMap<usize, u128>>,
+    /// Claims overlapping no other claim, in input order.
+    pub unique_claims: Vec<u32>,
+}
+
+impl Coverage {
+    /// The distinct numbers of claims, two or more, that overlap somewhere, if the area by depth
+    /// was worked out.
+    pub fn overlap_depths(&self) -> Option<Vec<usize>> {
+        self.area_by_depth.as_ref().map(|areas| areas.keys().cloned().filter(|&depth| depth > 1).collect())
+    }
+}
+
+fn cell_coverage(claims: &[Claim], by_depth: bool) -> Coverage {
+    let fabric = Fabric::new(claims);
+    let mut depths: HashMap<u128, usize> = HashMap::new();
+
+    for claim in claims.iter() {
+        for pos in claim.cells(&fabric) {
+            *depths.entry(pos).or_insert(0) += 1;
+        }
+    }
+
+    let mut area_by_depth = BTreeMap::new();
+    for &depth in depths.values() {
+        *area_by_depth.entry(depth).or_insert(0) += 1;
+    }
+
+    Coverage {
+        contested_area: area_by_depth.iter().filter(|&(&depth, _)| depth > 1).map(|(_, area)| area).sum(),
+        area_by_depth: if by_depth { Some(area_by_depth) } else { None },
+        unique_claims: claims
+            .iter()
+            .filter(|claim| claim.cells(&fabric).all(|pos| depths[&pos] == 1))
+            .map(|claim| claim.id)
+            .collect(),
+    }
+}
+
+fn cover(claims: &[Claim], engine: Engine, by_depth: bool) -> Coverage {
+    match engine {
+        Engine::Cells => cell_coverage(claims, by_depth),
+        Engine::Sweep => sweep::coverage(claims, by_depth),
+    }
+}
+
+/// Works out the contested area and unique claims using `engine`.
+pub fn coverage(claims: &[Claim], engine: Engine) -> Coverage {
+    cover(claims, engine, false)
+}
+
+/// As `coverage`, also working out the area at each depth. The sweep engine can take time
+/// quadratic in the number of claims to do so.
+pub fn coverage_by_depth(claims: &[Claim], engine: Engine) -> Coverage {
+    cover(claims, engine, true)
+}
+
+/// Parsed claims, laid out over the fabric by whichever part first needs it.
+#[derive(Debug)]
+pub struct Claims {
+    pub claims: Vec<Claim>,
+    coverage: RefCell<Option<Coverage>>,
+}
+
+impl Claims {
+    pub fn new(claims: Vec<Claim>) -> Claims {
+        Claims { claims, coverage: RefCell::new(None) }
+    }
+
+    /// The coverage from the sweep engine, worked out once and shared between the parts.
+    pub fn coverage(&self) -> Ref<'_, Coverage> {
+        if self.coverage.borrow().is_none() {
+            *self.coverage.borrow_mut() = Some(coverage(&self.claims, Engine::Sweep));
         }
 
-        return Some(claim.id)
+        Ref::map(self.coverage.borrow(), |coverage| coverage.as_ref().expect("Coverage was just worked out"))
+    }
+}
+
+/// Registry entry for day 3, using the sweep engine.
+pub struct Day3;
+
+impl Solution for Day3 {
+    type Input = Claims;
+
+    fn day(&self) -> u8 { 3 }
+
+    fn parse(&self, input: &str) -> Result<Claims, ParseError> {
+        parse_claims(input.as_bytes()).map(Claims::new)
+    }
+
+    fn part_1(&self, claims: &Claims) -> Result<Answer, SolveError> {
+        Ok(Answer::new("Conflicted area", claims.coverage().contested_area))
     }
 
-    None
+    fn part_2(&self, claims: &Claims) -> Result<Answer, SolveError> {
+        Ok(Answer::new(
+            "Unique claim id",
+            claims.coverage().unique_claims.first().map(|id| id.to_string()).unwrap_or_else(|| "none".to_string()),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -87,19 +254,30 @@ mod tests {
     #[test]
     fn can_parse_claim() {
         assert_eq!(
-            Some(Claim { id: 123, x: 3, y: 2, w: 5, h: 4 }),
+            Ok(Claim { id: 123, x: 3, y: 2, w: 5, h: 4 }),
             parse_claim("#123 @ 3,2: 5x4")
         );
 
         assert_eq!(
-            Some(Claim { id: 1, x: 1, y: 3, w: 4, h: 4 }),
+            Ok(Claim { id: 1, x: 1, y: 3, w: 4, h: 4 }),
             parse_claim("#1 @ 1,3: 4x4")
         );
 
-        assert_eq!(None, parse_claim(""))
+        assert!(parse_claim("").is_err());
+        assert!(parse_claim("#1 @ 1,3: 4x4 extra").is_err());
+        assert!(parse_claim("#1 @ 1,3: 99999999999x4").is_err());
+    }
+
+    #[test]
+    fn reports_bad_claim_line() {
+        let error = parse_claims("#1 @ 1,3: 4x4\n#2 @ 3,1 4x4\n".as_bytes()).unwrap_err();
+
+        assert_eq!(2, error.line);
+        assert_eq!("#2 @ 3,1 4x4", error.text);
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn can_find_conflict() {
         assert_eq!(
             4,
@@ -135,6 +313,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn can_find_intersect() {
         assert_eq!(
             Some(3),
@@ -168,4 +347,126 @@ mod tests {
             )
         )
     }
+
+    #[test]
+    fn should_not_alias_wide_claims() {
+        let claims = [
+            Claim { id: 1, x: 999, y: 0, w: 2, h: 1 },
+            Claim { id: 2, x: 0, y: 1, w: 1, h: 1 },
+        ];
+
+        assert_eq!(0, get_conflicted_area(&claims));
+        assert_eq!(Some(1), get_unique_claim_id(&claims));
+    }
+
+    #[test]
+    fn should_handle_claims_beyond_1000() {
+        let claims = [
+            Claim { id: 1, x: 1500, y: 2000, w: 10, h: 10 },
+            Claim { id: 2, x: 1505, y: 2005, w: 10, h: 10 },
+            Claim { id: 3, x: 4_000_000_000, y: 4_000_000_000, w: 3, h: 3 },
+            Claim { id: 4, x: 4_000_000_002, y: 4_000_000_000, w: 3, h: 2 },
+            Claim { id: 5, x: 1510, y: 2015, w: 5, h: 5 },
+        ];
+
+        assert_eq!(25 + 2, get_conflicted_area(&claims));
+        assert_eq!(Some(5), get_unique_claim_id(&claims));
+        assert_eq!(Ok(Claim { id: 7, x: 4_294_967_295, y: 1000, w: 4_294_967_295, h: 1 }), parse_claim("#7 @ 4294967295,1000: 4294967295x1"));
+        assert_eq!(8_589_934_590, parse_claim("#7 @ 4294967295,1000: 4294967295x1").unwrap().right());
+
+        let widest = Fabric::new(&[Claim { id: 1, x: u32::MAX, y: u32::MAX, w: u32::MAX, h: u32::MAX }]);
+        let corner = widest.position(8_589_934_589, 8_589_934_589);
+        assert_eq!(corner + 1, widest.position(0, 8_589_934_590));
+    }
+
+    fn example() -> Vec<Claim> {
+        vec![
+            Claim { id: 1, x: 1, y: 3, w: 4, h: 4 },
+            Claim { id: 2, x: 3, y: 1, w: 4, h: 4 },
+            Claim { id: 3, x: 5, y: 5, w: 2, h: 2 },
+            Claim { id: 4, x: 2, y: 4, w: 2, h: 2 },
+        ]
+    }
+
+    #[test]
+    fn should_report_coverage() {
+        for &engine in [Engine::Cells, Engine::Sweep].iter() {
+            let coverage = coverage_by_depth(&example(), engine);
+
+            assert_eq!(7, coverage.contested_area, "{:?}", engine);
+            assert_eq!(Some(vec![(1, 25), (2, 6), (3, 1)]), coverage.area_by_depth.map(|areas| areas.into_iter().collect()), "{:?}", engine);
+            assert_eq!(vec![3], coverage.unique_claims, "{:?}", engine);
+        }
+
+        assert_eq!(Some(vec![2, 3]), coverage_by_depth(&example(), Engine::Sweep).overlap_depths());
+        assert_eq!(None, coverage(&example(), Engine::Sweep).overlap_depths());
+    }
+
+    #[test]
+    fn should_handle_edge_cases() {
+        let claims = [
+            Claim { id: 1, x: 0, y: 0, w: 2, h: 2 },
+            Claim { id: 2, x: 2, y: 0, w: 2, h: 2 },
+            Claim { id: 3, x: 0, y: 2, w: 4, h: 1 },
+            Claim { id: 4, x: 1, y: 1, w: 0, h: 5 },
+            Claim { id: 5, x: 5, y: 0, w: 3, h: 3 },
+            Claim { id: 6, x: 6, y: 1, w: 1, h: 1 },
+        ];
+
+        for &engine in [Engine::Cells, Engine::Sweep].iter() {
+            let coverage = coverage(&claims, engine);
+            assert_eq!(1, coverage.contested_area, "{:?}", engine);
+            assert_eq!(vec![1, 2, 3, 4], coverage.unique_claims, "{:?}", engine);
+        }
+
+        assert_eq!(coverage(&[], Engine::Cells), coverage(&[], Engine::Sweep));
+    }
+
+    #[test]
+    fn sweep_should_match_cells() {
+        let mut rng = helpers::test_rng(17);
+        let mut next = |bound: u32| rng.below(u64::from(bound)) as u32;
+
+        for round in 0..40 {
+            let claims: Vec<Claim> = (0..5 + round)
+                .map(|id| Claim { id, x: next(40) + 990, y: next(40), w: next(12), h: next(12) })
+                .collect();
+
+            assert_eq!(coverage(&claims, Engine::Cells), coverage(&claims, Engine::Sweep), "{:?}", claims);
+            assert_eq!(coverage_by_depth(&claims, Engine::Cells), coverage_by_depth(&claims, Engine::Sweep), "{:?}", claims);
+        }
+    }
+
+    #[test]
+    fn sweep_should_handle_huge_claims() {
+        let claims = [
+            Claim { id: 1, x: 0, y: 0, w: u32::MAX, h: u32::MAX },
+            Claim { id: 2, x: u32::MAX - 1, y: u32::MAX - 1, w: u32::MAX, h: u32::MAX },
+            Claim { id: 3, x: u32::MAX, y: 0, w: u32::MAX, h: 1 },
+        ];
+        let coverage = coverage_by_depth(&claims, Engine::Sweep);
+        let side = u128::from(u32::MAX);
+
+        assert_eq!(1, coverage.contested_area);
+        assert_eq!(vec![3], coverage.unique_claims);
+        assert_eq!(Some(&(2 * side * side - 2 + side)), coverage.area_by_depth.unwrap().get(&1));
+    }
+
+    #[test]
+    fn sweep_should_find_overlaps_among_many_open_claims() {
+        let mut claims: Vec<Claim> = (0..5000).map(|i| Claim { id: i + 1, x: 2 * i, y: 0, w: 1, h: 10 }).collect();
+        claims.push(Claim { id: 5001, x: 0, y: 9, w: 5000, h: 1 });
+        claims.push(Claim { id: 5002, x: 5000, y: 5, w: 10, h: 1 });
+        let coverage = coverage(&claims, Engine::Sweep);
+
+        assert_eq!(2500 + 5, coverage.contested_area);
+        assert_eq!((2506..=5000).collect::<Vec<u32>>(), coverage.unique_claims);
+    }
+
+    #[test]
+    fn should_parse_engine() {
+        assert_eq!(Ok(Engine::Cells), "cells".parse());
+        assert_eq!(Ok(Engine::Sweep), "sweep".parse());
+        assert!("pixels".parse::<Engine>().is_err());
+    }
 }
@@ -1,20 +1,30 @@
+//! Day 4: Repose Record.
+
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::io::Read;
+use std::str::FromStr;
 
 use chrono::{DateTime, Timelike, TimeZone, Utc};
 use regex::Regex;
 
+use error::{ParseError, SolveError};
+use helpers;
+use solution::{Answer, Solution};
+
+/// The event recorded by a log entry.
 #[derive(Debug, Eq, PartialEq)]
 pub enum Log {
-    ShiftBegins(i32),
+    ShiftBegins(u32),
     FallsAsleep,
     WakesUp,
 }
 
+/// A timestamped log entry. Entries order by timestamp only.
 #[derive(Debug)]
 pub struct Entry {
-    timestamp: DateTime<Utc>,
-    log: Log,
+    pub timestamp: DateTime<Utc>,
+    pub log: Log,
 }
 
 impl PartialEq for Entry {
@@ -33,53 +43,61 @@ impl PartialOrd for Entry {
     }
 }
 
-fn parse_line(line: &str) -> Option<Entry> {
+fn parse_line(line: &str) -> Result<Entry, String> {
     lazy_static! {
         static ref LINE_MATCHER: Regex = Regex::new(r"\[([\d :-]{16})\] (Guard #(\d+) begins shift|falls asleep|wakes up)").unwrap();
     }
 
-    LINE_MATCHER
+    let matches = LINE_MATCHER
         .captures(line)
-        .map(|matches| {
-            let timestamp_str = matches.get(1).unwrap().as_str();
-            let timestamp_parsed = Utc.datetime_from_str(timestamp_str, "%Y-%m-%d %H:%M").unwrap();
-            let log = match matches.get(2).map(|m| m.as_str()) {
-                Some("falls asleep") => Some(Log::FallsAsleep),
-                Some("wakes up") => Some(Log::WakesUp),
-                Some(_) => Some(Log::ShiftBegins(matches.get(3).unwrap().as_str().parse().unwrap())),
-                _ => None
-            };
-
-            Entry {
-                timestamp: timestamp_parsed,
-                log: log.unwrap(),
-            }
-        })
+        .ok_or_else(|| "expected a log entry like '[1518-11-01 00:00] falls asleep'".to_string())?;
+
+    let timestamp_str = &matches[1];
+    let timestamp = Utc.datetime_from_str(timestamp_str, "%Y-%m-%d %H:%M")
+        .map_err(|e| format!("invalid timestamp '{}': {}", timestamp_str, e))?;
+
+    let log = match &matches[2] {
+        "falls asleep" => Log::FallsAsleep,
+        "wakes up" => Log::WakesUp,
+        _ => Log::ShiftBegins(matches[3].parse().map_err(|e| format!("invalid guard id '{}': {}", &matches[3], e))?),
+    };
+
+    Ok(Entry { timestamp, log })
+}
+
+impl FromStr for Entry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Entry, String> {
+        parse_line(s)
+    }
 }
 
-pub fn parse_logs(lines: &Vec<String>) -> Vec<Entry> {
-    let mut entries: Vec<Entry> = lines
-        .iter()
-        .map(|c| parse_line(c.as_str()))
-        .flatten()
-        .collect();
+/// Parses one log entry per line, skipping blank lines, and sorts them chronologically.
+pub fn parse_logs<R: Read>(input: R) -> Result<Vec<Entry>, ParseError> {
+    let mut entries: Vec<Entry> = helpers::lines(input).skip_blank().parse().collect::<Result<_, _>>()?;
 
     entries.sort();
 
-    entries
+    Ok(entries)
 }
 
-fn build_guards(logs: &Vec<Entry>) -> HashMap<i32, HashMap<u32, u32>> {
-    let mut guards: HashMap<i32, HashMap<u32, u32>> = HashMap::new();
-    let mut current_guard_id = -1;
+/// Counts how often each guard is asleep at each minute. Sleeps logged before any shift begins
+/// belong to no guard and are ignored.
+fn build_guards(logs: &[Entry]) -> HashMap<u32, HashMap<u32, u32>> {
+    let mut guards: HashMap<u32, HashMap<u32, u32>> = HashMap::new();
+    let mut current_guard_id = None;
     let mut start_minute: u32 = 0;
 
     for entry in logs {
         match entry.log {
-            Log::ShiftBegins(id) => current_guard_id = id,
+            Log::ShiftBegins(id) => current_guard_id = Some(id),
             Log::FallsAsleep => start_minute = entry.timestamp.minute(),
             Log::WakesUp => {
-                let mut guard = guards.entry(current_guard_id).or_insert(HashMap::new());
+                let guard = match current_guard_id {
+                    Some(id) => guards.entry(id).or_default(),
+                    None => continue,
+                };
                 for min in start_minute..entry.timestamp.minute() {
                     *guard.entry(min).or_insert(0) += 1;
                 }
@@ -90,30 +108,59 @@ fn build_guards(logs: &Vec<Entry>) -> HashMap<i32, HashMap<u32, u32>> {
     guards
 }
 
-pub fn part_1(logs: &Vec<Entry>) -> (i32, u32) {
+/// Finds the guard who sleeps the most and the minute they are most often asleep, or `None` if
+/// no guard ever sleeps.
+pub fn part_1(logs: &[Entry]) -> Option<(u32, u32)> {
     let guards = build_guards(logs);
 
-    let (&id, _) =
+    let (&id, minutes) =
         guards
             .iter()
-            .map(|(id, minutes)| (id, minutes.values().fold(0, |acc, v| acc + v)))
-            .max_by(|(_, a), (_, b)| a.cmp(&b)).unwrap();
+            .filter(|(_, minutes)| !minutes.is_empty())
+            .max_by(|(_, a), (_, b)| a.values().sum::<u32>().cmp(&b.values().sum::<u32>()))?;
 
-    let (&minute, _) = guards.get(&id).unwrap().iter().max_by(|(_, a), (_, b)| a.cmp(&b)).unwrap();
+    let (&minute, _) = minutes.iter().max_by(|(_, a), (_, b)| a.cmp(b))?;
 
-    (id, minute)
+    Some((id, minute))
 }
 
-pub fn part_2(logs: &Vec<Entry>) -> (i32, u32) {
+/// Finds the guard most frequently asleep on the same minute, and that minute, or `None` if no
+/// guard ever sleeps.
+pub fn part_2(logs: &[Entry]) -> Option<(u32, u32)> {
     let guards = build_guards(logs);
 
     let (&id, (&minute, _)) =
         guards
             .iter()
-            .map(|(id, minutes)| (id, minutes.iter().max_by(|(_, a),(_,b)| a.cmp(&b)).unwrap()))
-            .max_by(|(_, (_,a)),(_,(_,b))| a.cmp(&b)).unwrap();
+            .filter_map(|(id, minutes)| minutes.iter().max_by(|(_, a),(_,b)| a.cmp(b)).map(|minute| (id, minute)))
+            .max_by(|(_, (_,a)),(_,(_,b))| a.cmp(b))?;
+
+    Some((id, minute))
+}
+
+fn guard_answer(found: Option<(u32, u32)>) -> String {
+    found.map(|(id, minute)| (u64::from(id) * u64::from(minute)).to_string()).unwrap_or_else(|| "none".to_string())
+}
+
+/// Registry entry for day 4.
+pub struct Day4;
+
+impl Solution for Day4 {
+    type Input = Vec<Entry>;
 
-    (id, minute)
+    fn day(&self) -> u8 { 4 }
+
+    fn parse(&self, input: &str) -> Result<Vec<Entry>, ParseError> {
+        parse_logs(input.as_bytes())
+    }
+
+    fn part_1(&self, logs: &Vec<Entry>) -> Result<Answer, SolveError> {
+        Ok(Answer::new("Guard ID x Sleepiest Minute", guard_answer(part_1(logs))))
+    }
+
+    fn part_2(&self, logs: &Vec<Entry>) -> Result<Answer, SolveError> {
+        Ok(Answer::new("Guard ID x Most Frequent Minute", guard_answer(part_2(logs))))
+    }
 }
 
 #[cfg(test)]
@@ -121,9 +168,10 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::zero_prefixed_literal)]
     fn can_parse_claim() {
         assert_eq!(
-            Some(
+            Ok(
                 Entry {
                     timestamp: Utc.ymd(1518, 11, 01).and_hms(0, 0, 0),
                     log: Log::ShiftBegins(10),
@@ -132,7 +180,7 @@ mod tests {
             parse_line("[1518-11-01 00:00] Guard #10 begins shift")
         );
         assert_eq!(
-            Some(
+            Ok(
                 Entry {
                     timestamp: Utc.ymd(1518, 11, 01).and_hms(0, 5, 0),
                     log: Log::FallsAsleep,
@@ -141,7 +189,7 @@ mod tests {
             parse_line("[1518-11-01 00:05] falls asleep")
         );
         assert_eq!(
-            Some(
+            Ok(
                 Entry {
                     timestamp: Utc.ymd(1518, 11, 01).and_hms(0, 25, 0),
                     log: Log::WakesUp,
@@ -150,16 +198,17 @@ mod tests {
             parse_line("[1518-11-01 00:25] wakes up")
         );
 
-        assert_eq!(None, parse_line(""))
+        assert!(parse_line("").is_err());
+        assert!(parse_line("[1518-13-01 00:25] wakes up").is_err());
     }
 
     #[test]
     fn test_part_1() {
         assert_eq!(
-            (10, 24),
+            Some((10, 24)),
             part_1(
                 &parse_logs(
-                    &vec![
+                    [
                         "[1518-11-01 00:00] Guard #10 begins shift",
                         "[1518-11-01 00:05] falls asleep",
                         "[1518-11-01 00:25] wakes up",
@@ -177,16 +226,16 @@ mod tests {
                         "[1518-11-05 00:03] Guard #99 begins shift",
                         "[1518-11-05 00:45] falls asleep",
                         "[1518-11-05 00:55] wakes up"
-                    ].iter().map(|x| x.to_string()).collect()
-                )
+                    ].join("\n").as_bytes()
+                ).unwrap()
             )
         );
 
         assert_eq!(
-            (10, 24),
+            Some((10, 24)),
             part_1(
                 &parse_logs(
-                    &vec![
+                    [
                         "[1518-11-01 00:05] falls asleep",
                         "[1518-11-01 00:25] wakes up",
                         "[1518-11-01 00:30] falls asleep",
@@ -204,8 +253,8 @@ mod tests {
                         "[1518-11-01 23:58] Guard #99 begins shift",
                         "[1518-11-04 00:46] wakes up",
                         "[1518-11-05 00:55] wakes up"
-                    ].iter().map(|x| x.to_string()).collect()
-                )
+                    ].join("\n").as_bytes()
+                ).unwrap()
             )
         )
     }
@@ -213,10 +262,10 @@ mod tests {
     #[test]
     fn test_part_2() {
         assert_eq!(
-            (99, 45),
+            Some((99, 45)),
             part_2(
                 &parse_logs(
-                    &vec![
+                    [
                         "[1518-11-01 00:00] Guard #10 begins shift",
                         "[1518-11-01 00:05] falls asleep",
                         "[1518-11-01 00:25] wakes up",
@@ -234,16 +283,16 @@ mod tests {
                         "[1518-11-05 00:03] Guard #99 begins shift",
                         "[1518-11-05 00:45] falls asleep",
                         "[1518-11-05 00:55] wakes up"
-                    ].iter().map(|x| x.to_string()).collect()
-                )
+                    ].join("\n").as_bytes()
+                ).unwrap()
             )
         );
 
         assert_eq!(
-            (99, 45),
+            Some((99, 45)),
             part_2(
                 &parse_logs(
-                    &vec![
+                    [
                         "[1518-11-01 00:05] falls asleep",
                         "[1518-11-01 00:25] wakes up",
                         "[1518-11-01 00:30] falls asleep",
@@ -261,10 +310,34 @@ mod tests {
                         "[1518-11-01 23:58] Guard #99 begins shift",
                         "[1518-11-04 00:46] wakes up",
                         "[1518-11-05 00:55] wakes up"
-                    ].iter().map(|x| x.to_string()).collect()
-                )
+                    ].join("\n").as_bytes()
+                ).unwrap()
             )
         )
     }
-}
 
+    #[test]
+    fn should_find_nothing_without_sleeps() {
+        let logs = parse_logs("[1518-11-01 00:00] Guard #10 begins shift".as_bytes()).unwrap();
+
+        assert_eq!(None, part_1(&[]));
+        assert_eq!(None, part_2(&[]));
+        assert_eq!(None, part_1(&logs));
+        assert_eq!(None, part_2(&logs));
+        assert_eq!("none", Day4.part_2(&logs).unwrap().value);
+    }
+
+    #[test]
+    fn should_answer_for_large_guard_ids() {
+        let logs = parse_logs(
+            ["[1518-11-01 00:00] Guard #4000000000 begins shift", "[1518-11-01 00:58] falls asleep", "[1518-11-01 00:59] wakes up"]
+                .join("\n")
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(Some((4_000_000_000, 58)), part_1(&logs));
+        assert_eq!("232000000000", Day4.part_1(&logs).unwrap().value);
+        assert_eq!("232000000000", Day4.part_2(&logs).unwrap().value);
+    }
+}
@@ -1,37 +1,317 @@
-use std::collections::HashSet;
-use std::iter::FromIterator;
-
-pub fn collapse_polymer(polymer: &String) -> String {
-    polymer.chars().fold(
-        "".to_string(),
-        |acc, c| {
-            let (rest, p) = if acc == "" { ("", "") } else { acc.split_at(acc.len() - 1) };
-            if c.to_string() != p && c.to_string().to_lowercase() == p.to_lowercase() {
-                rest.to_string()
-            } else {
-                format!("{}{}", acc, c).trim().to_string()
+//! Day 5: Alchemical Reduction.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use error::{ParseError, SolveError};
+use solution::{Answer, Solution};
+
+pub mod chunked;
+pub mod incremental;
+mod rope;
+pub mod rules;
+pub mod search;
+
+pub use self::chunked::Segment;
+pub use self::incremental::Polymer;
+pub use self::rules::{CasePair, PairTable, Predicate, ReactionRule};
+pub use self::search::{set_removal_report, set_removal_report_with_rule, SetRemovalReport, Strategy};
+
+fn unit_types<R: ReactionRule>(units: &[u8], rule: &R) -> Vec<u8> {
+    let mut types: Vec<u8> = units.iter().map(|&u| rule.unit_type(u)).collect();
+    types.sort();
+    types.dedup();
+    types
+}
+
+/// Reduces a stream of units using a stack: each unit either annihilates the unit on top of the
+/// stack or is pushed onto it. Whitespace is ignored.
+fn collapse_units<I: IntoIterator<Item=u8>, R: ReactionRule>(units: I, rule: &R) -> Vec<u8> {
+    let mut stack = Vec::new();
+
+    for unit in units {
+        if unit.is_ascii_whitespace() {
+            continue;
+        }
+
+        match stack.last() {
+            Some(&top) if rule.reacts(top, unit) => { stack.pop(); }
+            _ => stack.push(unit),
+        }
+    }
+
+    stack
+}
+
+fn to_polymer(units: Vec<u8>) -> String {
+    String::from_utf8(units).expect("Polymers are ASCII")
+}
+
+/// Repeatedly removes adjacent units of the same type and opposite polarity, e.g. `aA`.
+pub fn collapse_polymer(polymer: &str) -> String {
+    collapse_polymer_with_rule(polymer, &CasePair)
+}
+
+/// Collapses `polymer`, removing adjacent units whenever `rule` says they react.
+pub fn collapse_polymer_with_rule<R: ReactionRule>(polymer: &str, rule: &R) -> String {
+    to_polymer(collapse_units(polymer.bytes(), rule))
+}
+
+fn collapse_without<R: ReactionRule>(units: &[u8], to_remove: u8, rule: &R) -> Vec<u8> {
+    collapse_units(units.iter().cloned().filter(|&u| rule.unit_type(u) != to_remove), rule)
+}
+
+fn units_of(polymer: &str) -> Vec<u8> {
+    polymer.bytes().filter(|u| !u.is_ascii_whitespace()).collect()
+}
+
+fn remove_best_unit<R: ReactionRule>(units: &[u8], rule: &R) -> String {
+    unit_types(units, rule)
+        .into_iter()
+        .map(|to_remove| collapse_without(units, to_remove, rule))
+        .min_by_key(|units| units.len())
+        .map(to_polymer)
+        .unwrap_or_default()
+}
+
+/// Removes whichever unit type gives the shortest collapsed polymer, and returns that polymer.
+/// Ties go to the alphabetically first unit type.
+pub fn remove_best_unit_and_collapse(polymer: &str) -> String {
+    // Removing a unit type never creates a case-pair reaction that collapsing first would
+    // prevent, so the already collapsed polymer is a much shorter starting point.
+    remove_best_unit(&collapse_units(polymer.bytes(), &CasePair), &CasePair)
+}
+
+/// As `remove_best_unit_and_collapse`, with unit types and reactions defined by `rule`. Each
+/// candidate is collapsed from the full polymer, as arbitrary rules may not allow the shortcut
+/// taken for the default rule.
+pub fn remove_best_unit_and_collapse_with_rule<R: ReactionRule>(polymer: &str, rule: &R) -> String {
+    remove_best_unit(&units_of(polymer), rule)
+}
+
+/// The collapsed polymer length after removing each unit type, in unit order.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RemovalReport {
+    pub lengths: Vec<(char, usize)>,
+}
+
+impl RemovalReport {
+    /// The unit whose removal leaves the shortest polymer, preferring the earliest letter on ties.
+    pub fn best(&self) -> Option<(char, usize)> {
+        self.lengths.iter().cloned().min_by_key(|&(unit, len)| (len, unit))
+    }
+}
+
+fn removal_report<R: ReactionRule + Sync>(units: &[u8], candidates: &[u8], workers: usize, rule: &R) -> RemovalReport {
+
+    let workers = match workers {
+        0 => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        n => n,
+    }.min(candidates.len()).max(1);
+
+    let next = AtomicUsize::new(0);
+
+    let mut lengths: Vec<(char, usize)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| scope.spawn(|| {
+                let mut results = Vec::new();
+
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    match candidates.get(i) {
+                        Some(&unit) => results.push((unit as char, collapse_without(units, unit, rule).len())),
+                        None => return results,
+                    }
+                }
+            }))
+            .collect();
+
+        handles.into_iter().flat_map(|h| h.join().expect("Removal worker panicked")).collect()
+    });
+
+    lengths.sort();
+
+    RemovalReport { lengths }
+}
+
+/// Evaluates removing every unit type present in `polymer`, spreading the candidates across
+/// `workers` threads. A worker count of zero uses the available parallelism.
+pub fn unit_removal_report(polymer: &str, workers: usize) -> RemovalReport {
+    // Unit types that annihilate completely are still candidates, so they are taken from the
+    // original polymer while each removal collapses from the reduced one.
+    let candidates = unit_types(&units_of(polymer), &CasePair);
+    removal_report(&collapse_units(polymer.bytes(), &CasePair), &candidates, workers, &CasePair)
+}
+
+/// As `unit_removal_report`, with unit types and reactions defined by `rule`.
+pub fn unit_removal_report_with_rule<R: ReactionRule + Sync>(polymer: &str, workers: usize, rule: &R) -> RemovalReport {
+    let units = units_of(polymer);
+    removal_report(&units, &unit_types(&units, rule), workers, rule)
+}
+
+/// A pair of units annihilating during collapse.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Reaction {
+    /// Byte offsets of the two units in the original input.
+    pub left: usize,
+    pub right: usize,
+    pub units: (char, char),
+    /// The type of the left unit, as given by the rule that made the pair react.
+    pub unit_type: char,
+    /// Number of units left on the stack once the pair is removed.
+    pub depth: usize,
+}
+
+/// Every reaction performed while collapsing a polymer, in order.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Trace {
+    pub collapsed: String,
+    pub reactions: Vec<Reaction>,
+    pub max_depth: usize,
+}
+
+impl Trace {
+    /// How many reactions each unit type took part in.
+    pub fn reactions_by_unit(&self) -> BTreeMap<char, usize> {
+        let mut counts = BTreeMap::new();
+
+        for reaction in self.reactions.iter() {
+            *counts.entry(reaction.unit_type).or_insert(0) += 1;
+        }
+
+        counts
+    }
+
+    /// The polymer after each reaction, starting with `original` itself. Only sensible for small
+    /// inputs, as every step is a full copy.
+    pub fn replay(&self, original: &str) -> Vec<String> {
+        let mut removed = vec![false; original.len()];
+        let remaining = |removed: &[bool]| -> String {
+            original.char_indices()
+                .filter(|&(i, c)| !removed[i] && !c.is_whitespace())
+                .map(|(_, c)| c)
+                .collect()
+        };
+
+        let mut steps = vec![remaining(&removed)];
+        for reaction in self.reactions.iter() {
+            removed[reaction.left] = true;
+            removed[reaction.right] = true;
+            steps.push(remaining(&removed));
+        }
+
+        steps
+    }
+}
+
+/// Collapses `polymer` as `collapse_polymer` does, recording each reaction along the way.
+pub fn collapse_with_trace(polymer: &str) -> Trace {
+    collapse_with_trace_with_rule(polymer, &CasePair)
+}
+
+/// As `collapse_with_trace`, with unit types and reactions defined by `rule`.
+pub fn collapse_with_trace_with_rule<R: ReactionRule>(polymer: &str, rule: &R) -> Trace {
+    let mut stack: Vec<(usize, u8)> = Vec::new();
+    let mut reactions = Vec::new();
+    let mut max_depth = 0;
+
+    for (position, unit) in polymer.bytes().enumerate() {
+        if unit.is_ascii_whitespace() {
+            continue;
+        }
+
+        match stack.last() {
+            Some(&(left, top)) if rule.reacts(top, unit) => {
+                stack.pop();
+                reactions.push(Reaction {
+                    left,
+                    right: position,
+                    units: (top as char, unit as char),
+                    unit_type: rule.unit_type(top) as char,
+                    depth: stack.len(),
+                });
             }
-        },
-    )
+            _ => {
+                stack.push((position, unit));
+                max_depth = max_depth.max(stack.len());
+            }
+        }
+    }
+
+    Trace {
+        collapsed: to_polymer(stack.into_iter().map(|(_, unit)| unit).collect()),
+        reactions,
+        max_depth,
+    }
+}
+
+/// The original string-rebuilding implementation, kept to cross-check and benchmark against.
+pub mod reference {
+    use std::collections::HashSet;
+    use std::iter::FromIterator;
+
+    pub fn collapse_polymer(polymer: &str) -> String {
+        polymer.chars().fold(
+            "".to_string(),
+            |acc, c| {
+                let (rest, p) = if acc.is_empty() { ("", "") } else { acc.split_at(acc.len() - 1) };
+                if c.to_string() != p && c.to_string().to_lowercase() == p.to_lowercase() {
+                    rest.to_string()
+                } else {
+                    format!("{}{}", acc, c).trim().to_string()
+                }
+            },
+        )
+    }
+
+    pub fn remove_best_unit_and_collapse(polymer: &str) -> String {
+        let chars: HashSet<char> = HashSet::from_iter(polymer.to_lowercase().chars());
+
+        chars.iter()
+            .map(|to_remove| {
+                let removed: String = polymer.chars().filter(|c| c.to_lowercase().to_string() != to_remove.to_string()).collect();
+                collapse_polymer(&removed)
+            })
+            .min_by(|a, b| a.len().cmp(&b.len()))
+            .unwrap_or("".to_string())
+    }
 }
 
-pub fn remove_best_unit_and_collapse(polymer: &String) -> String {
-    let chars: HashSet<char> = HashSet::from_iter(polymer.to_lowercase().chars());
+/// Registry entry for day 5.
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = String;
+
+    fn day(&self) -> u8 { 5 }
+
+    fn parse(&self, input: &str) -> Result<String, ParseError> {
+        let polymer = input.trim();
+
+        match polymer.char_indices().find(|(_, c)| !c.is_ascii_alphabetic()) {
+            Some((i, c)) => Err(ParseError::new(1, &c.to_string(), &format!("invalid unit at position {}", i))),
+            None => Ok(polymer.to_string()),
+        }
+    }
+
+    fn part_1(&self, polymer: &String) -> Result<Answer, SolveError> {
+        Ok(Answer::new("Polymer length", collapse_polymer(polymer).len()))
+    }
 
-    chars.iter()
-        .map(|to_remove| {
-            let removed: String = polymer.chars().filter(|c| c.to_lowercase().to_string() != to_remove.to_string()).collect();
-            collapse_polymer(&removed)
-        })
-        .min_by(|a, b| a.len().cmp(&b.len()))
-        .unwrap_or("".to_string())
+    fn part_2(&self, polymer: &String) -> Result<Answer, SolveError> {
+        let best = unit_removal_report(polymer, 0).best().map(|(_, len)| len).unwrap_or(0);
+        Ok(Answer::new("Polymer length with unit removed", best))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use helpers;
 
     #[test]
+    #[allow(clippy::unnecessary_to_owned)]
     fn should_collapse_polymer() {
         assert_eq!("", collapse_polymer(&"aA".to_string()));
         assert_eq!("", collapse_polymer(&"abBA".to_string()));
@@ -42,6 +322,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_to_owned)]
     fn should_remove_best_unit() {
         assert_eq!("", remove_best_unit_and_collapse(&"aA".to_string()));
         assert_eq!("", remove_best_unit_and_collapse(&"abBA".to_string()));
@@ -50,4 +331,134 @@ mod tests {
         assert_eq!("", remove_best_unit_and_collapse(&"aabCcAAB".to_string()));
         assert_eq!("daDA", remove_best_unit_and_collapse(&"dabAcCaCBAcCcaDA".to_string()));
     }
+
+    #[test]
+    fn should_report_every_unit_removal() {
+        let report = unit_removal_report("dabAcCaCBAcCcaDA", 2);
+
+        assert_eq!(vec![('a', 6), ('b', 8), ('c', 4), ('d', 6)], report.lengths);
+        assert_eq!(Some(('c', 4)), report.best());
+
+        assert_eq!(report, unit_removal_report("dabAcCaCBAcCcaDA", 0));
+        assert_eq!(report, unit_removal_report("dabAcCaCBAcCcaDA", 16));
+        assert_eq!(None, unit_removal_report("", 4).best());
+    }
+
+    #[test]
+    fn should_report_units_that_annihilate_completely() {
+        assert_eq!(Some(('a', 0)), unit_removal_report("aA", 4).best());
+        assert_eq!(vec![('a', 1), ('b', 1), ('x', 2)], unit_removal_report("xXab", 2).lengths);
+        assert_eq!(unit_removal_report("xXab", 1), unit_removal_report_with_rule("xXab", 1, &CasePair));
+    }
+
+    #[test]
+    fn should_break_ties_by_unit() {
+        let report = unit_removal_report("abAB", 3);
+
+        assert_eq!(vec![('a', 0), ('b', 0)], report.lengths);
+        assert_eq!(Some(('a', 0)), report.best());
+    }
+
+    #[test]
+    fn should_collapse_with_rules() {
+        let polymer = "dabAcCaCBAcCcaDA";
+
+        assert_eq!(collapse_polymer(polymer), collapse_polymer_with_rule(polymer, &CasePair));
+        assert_eq!(
+            collapse_polymer(polymer),
+            collapse_polymer_with_rule(polymer, &Predicate(|a: u8, b: u8| a != b && a.eq_ignore_ascii_case(&b)))
+        );
+
+        let table = PairTable::parse("ab\ncd\n").unwrap();
+        assert_eq!("", collapse_polymer_with_rule("abcddcba", &table));
+        assert_eq!("aA", collapse_polymer_with_rule("aA", &table));
+        assert_eq!("", collapse_polymer_with_rule("acdb", &table));
+    }
+
+    #[test]
+    fn should_remove_units_with_rules() {
+        // Only identical units react, while unit types still ignore case as by default.
+        let rule = Predicate(|a: u8, b: u8| a == b);
+
+        assert_eq!("", remove_best_unit_and_collapse_with_rule("abbacca", &rule));
+        assert_eq!(
+            vec![('a', 0), ('b', 1), ('c', 1)],
+            unit_removal_report_with_rule("abbacca", 2, &rule).lengths
+        );
+
+        assert_eq!(
+            unit_removal_report("dabAcCaCBAcCcaDA", 1),
+            unit_removal_report_with_rule("dabAcCaCBAcCcaDA", 2, &CasePair)
+        );
+    }
+
+    #[test]
+    fn should_trace_reactions() {
+        let trace = collapse_with_trace("dabAcCaCBAcCcaDA");
+
+        assert_eq!("dabCBAcaDA", trace.collapsed);
+        assert_eq!(
+            vec![
+                Reaction { left: 4, right: 5, units: ('c', 'C'), unit_type: 'c', depth: 4 },
+                Reaction { left: 3, right: 6, units: ('A', 'a'), unit_type: 'a', depth: 3 },
+                Reaction { left: 10, right: 11, units: ('c', 'C'), unit_type: 'c', depth: 6 },
+            ],
+            trace.reactions
+        );
+        assert_eq!(10, trace.max_depth);
+
+        let counts: Vec<(char, usize)> = trace.reactions_by_unit().into_iter().collect();
+        assert_eq!(vec![('a', 1), ('c', 2)], counts);
+    }
+
+    #[test]
+    fn should_trace_reactions_with_rules() {
+        let table = PairTable::parse("ab\ncd\n").unwrap();
+        let trace = collapse_with_trace_with_rule("acdbA", &table);
+
+        assert_eq!(collapse_polymer_with_rule("acdbA", &table), trace.collapsed);
+        assert_eq!(
+            vec![
+                Reaction { left: 1, right: 2, units: ('c', 'd'), unit_type: 'c', depth: 1 },
+                Reaction { left: 0, right: 3, units: ('a', 'b'), unit_type: 'a', depth: 0 },
+            ],
+            trace.reactions
+        );
+        assert_eq!(vec!["acdbA", "abA", "A"], trace.replay("acdbA"));
+    }
+
+    #[test]
+    fn should_replay_trace() {
+        let polymer = "dabAcCaCBAcCcaDA";
+
+        assert_eq!(
+            vec!["dabAcCaCBAcCcaDA", "dabAaCBAcCcaDA", "dabCBAcCcaDA", "dabCBAcaDA"],
+            collapse_with_trace(polymer).replay(polymer)
+        );
+    }
+
+    #[test]
+    fn should_trace_positions_in_untrimmed_input() {
+        let polymer = "  baA\n";
+        let trace = collapse_with_trace(polymer);
+
+        assert_eq!((3, 4), (trace.reactions[0].left, trace.reactions[0].right));
+        assert_eq!(vec!["baA", "b"], trace.replay(polymer));
+    }
+
+    #[test]
+    fn should_match_reference_implementation() {
+        for seed in 0..50 {
+            let polymer = helpers::test_rng(seed).string(200, b"aAbBcC");
+
+            assert_eq!(reference::collapse_polymer(&polymer), collapse_polymer(&polymer));
+            assert_eq!(collapse_polymer(&polymer), collapse_with_trace(&polymer).collapsed);
+            assert_eq!(
+                reference::remove_best_unit_and_collapse(&polymer).len(),
+                remove_best_unit_and_collapse(&polymer).len()
+            );
+        }
+
+        assert_eq!(reference::collapse_polymer("aB c\nCb"), collapse_polymer("aB c\nCb"));
+    }
 }
@@ -0,0 +1,196 @@
+//! Reducing polymers piece by piece. A reduced segment can only react with its neighbours at its
+//! ends, so segments reduced independently can be joined by annihilating units across the join.
+//! This lets huge polymers be streamed from disk in chunks that are reduced in parallel.
+//!
+//! Joining gives the same result as the sequential collapse for rules where reactions are
+//! symmetric and each unit has at most one partner, as with the default
+//! [`CasePair`](../rules/struct.CasePair.html) rule.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::thread;
+
+use super::{collapse_units, to_polymer};
+use super::rules::ReactionRule;
+
+/// A fully reduced stretch of polymer.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Segment {
+    units: Vec<u8>,
+}
+
+impl Segment {
+    /// Reduces `units` into a segment.
+    pub fn reduce<R: ReactionRule>(units: &[u8], rule: &R) -> Segment {
+        Segment { units: collapse_units(units.iter().cloned(), rule) }
+    }
+
+    /// Appends `other`, annihilating units across the join.
+    pub fn merge<R: ReactionRule>(mut self, other: Segment, rule: &R) -> Segment {
+        let matched = self.matched(&other, rule);
+
+        self.units.truncate(self.units.len() - matched);
+        self.units.extend_from_slice(&other.units[matched..]);
+        self
+    }
+
+    /// As `merge`, leaving both segments in place.
+    pub fn join<R: ReactionRule>(&self, other: &Segment, rule: &R) -> Segment {
+        let matched = self.matched(other, rule);
+        let left = &self.units[..self.units.len() - matched];
+        let right = &other.units[matched..];
+
+        let mut units = Vec::with_capacity(left.len() + right.len());
+        units.extend_from_slice(left);
+        units.extend_from_slice(right);
+
+        Segment { units }
+    }
+
+    /// How many units from each side annihilate when `other` is appended.
+    fn matched<R: ReactionRule>(&self, other: &Segment, rule: &R) -> usize {
+        self.units
+            .iter()
+            .rev()
+            .zip(other.units.iter())
+            .take_while(|&(&left, &right)| rule.reacts(left, right))
+            .count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.units.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.units
+    }
+
+    pub fn into_polymer(self) -> String {
+        to_polymer(self.units)
+    }
+}
+
+fn reduce_batch<R: ReactionRule + Sync>(chunks: &[Vec<u8>], rule: &R) -> Vec<Segment> {
+    if chunks.len() == 1 {
+        return vec![Segment::reduce(&chunks[0], rule)];
+    }
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .map(|chunk| scope.spawn(move || Segment::reduce(chunk, rule)))
+            .collect();
+
+        handles.into_iter().map(|h| h.join().expect("Reduction worker panicked")).collect()
+    })
+}
+
+fn read_chunk<Rd: Read>(reader: &mut Rd, size: usize) -> io::Result<Vec<u8>> {
+    let mut chunk = Vec::with_capacity(size);
+    reader.by_ref().take(size as u64).read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
+
+/// Reduces a polymer streamed from `reader`, `chunk_size` bytes at a time, reducing up to
+/// `workers` chunks concurrently. Only the reduced polymer and the chunks in flight are held in
+/// memory. Whitespace is ignored.
+pub fn reduce_reader<Rd: Read, R: ReactionRule + Sync>(
+    mut reader: Rd,
+    chunk_size: usize,
+    workers: usize,
+    rule: &R,
+) -> io::Result<Segment> {
+    let chunk_size = chunk_size.max(1);
+    let workers = workers.max(1);
+    let mut reduced = Segment::default();
+
+    loop {
+        let mut batch = Vec::with_capacity(workers);
+        let mut finished = false;
+
+        while batch.len() < workers {
+            let chunk = read_chunk(&mut reader, chunk_size)?;
+            finished = chunk.len() < chunk_size;
+            if !chunk.is_empty() {
+                batch.push(chunk);
+            }
+            if finished {
+                break;
+            }
+        }
+
+        for segment in reduce_batch(&batch, rule) {
+            reduced = reduced.merge(segment, rule);
+        }
+
+        if finished {
+            return Ok(reduced);
+        }
+    }
+}
+
+/// Reduces the polymer in the file at `path`. See [`reduce_reader`](fn.reduce_reader.html).
+pub fn reduce_file<R: ReactionRule + Sync>(path: &str, chunk_size: usize, workers: usize, rule: &R) -> io::Result<Segment> {
+    reduce_reader(File::open(path)?, chunk_size, workers, rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use day5::{collapse_polymer, collapse_polymer_with_rule};
+    use day5::rules::{CasePair, PairTable};
+    use helpers;
+
+    #[test]
+    fn should_merge_segments() {
+        let left = Segment::reduce(b"dabAc", &CasePair);
+        let right = Segment::reduce(b"CaCBAcCcaDA", &CasePair);
+
+        assert_eq!("dabCBAcaDA", left.clone().merge(right.clone(), &CasePair).into_polymer());
+        assert_eq!(
+            Segment::reduce(b"ab", &CasePair),
+            Segment::reduce(b"ab", &CasePair).merge(Segment::default(), &CasePair)
+        );
+        assert!(Segment::reduce(b"abc", &CasePair).merge(Segment::reduce(b"CBA", &CasePair), &CasePair).is_empty());
+        assert_eq!("dabCBAcaDA", left.join(&right, &CasePair).into_polymer());
+        assert_eq!("abD", Segment::reduce(b"abc", &CasePair).join(&Segment::reduce(b"CD", &CasePair), &CasePair).into_polymer());
+    }
+
+    #[test]
+    fn should_match_sequential_collapse() {
+        for seed in 0..20 {
+            let polymer = helpers::test_rng(seed).string(500, b"aAbBcC");
+            let expected = collapse_polymer(&polymer);
+
+            for &(chunk_size, workers) in [(1, 1), (3, 2), (7, 4), (64, 3), (1000, 2)].iter() {
+                let reduced = reduce_reader(polymer.as_bytes(), chunk_size, workers, &CasePair).unwrap();
+                assert_eq!(expected.len(), reduced.len());
+                assert_eq!(expected, reduced.into_polymer());
+            }
+        }
+    }
+
+    #[test]
+    fn should_match_sequential_collapse_with_pair_table() {
+        let table = PairTable::parse("ab\ncd\nee\n").unwrap();
+
+        for seed in 0..20 {
+            let polymer = helpers::test_rng(seed).string(300, b"abcde");
+            let reduced = reduce_reader(polymer.as_bytes(), 5, 3, &table).unwrap();
+
+            assert_eq!(collapse_polymer_with_rule(&polymer, &table), reduced.into_polymer());
+        }
+    }
+
+    #[test]
+    fn should_reduce_file() {
+        let reduced = reduce_file("resources/day5.txt", 4096, 4, &CasePair).unwrap();
+
+        assert_eq!(10368, reduced.len());
+        assert!(reduce_file("resources/not-a-file.nope", 4096, 4, &CasePair).is_err());
+    }
+}
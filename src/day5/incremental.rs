@@ -0,0 +1,340 @@
+//! A polymer that can be edited in place while keeping its collapsed form cheap to recompute.
+//!
+//! Units are stored in blocks, the leaves of a height-balanced tree whose every node caches the
+//! unit count and reduced units of the blocks below it. Reduced units are held in a
+//! [`Rope`](../rope/struct.Rope.html), so a node shares them with its children rather than
+//! copying them. An edit re-reduces its block and re-joins the O(log n) nodes above it, each
+//! join costing the units that annihilate across it plus O(log n). Blocks that grow too large
+//! are split, and empty ones dropped, in place by rotating the nodes above them. Reading the
+//! collapsed length only looks at the root.
+
+use super::rope::Rope;
+use super::rules::{CasePair, ReactionRule};
+use super::{collapse_units, to_polymer, units_of};
+
+const BLOCK_SIZE: usize = 256;
+
+#[derive(Debug, Clone)]
+enum Body {
+    Leaf(Vec<u8>),
+    Branch(Box<Blocks>, Box<Blocks>),
+}
+
+/// A node of the tree of blocks.
+#[derive(Debug, Clone)]
+struct Blocks {
+    len: usize,
+    height: usize,
+    reduced: Rope,
+    body: Body,
+}
+
+fn leaf<R: ReactionRule>(units: Vec<u8>, rule: &R) -> Box<Blocks> {
+    let reduced = Rope::new(&collapse_units(units.iter().cloned(), rule));
+    Box::new(Blocks { len: units.len(), height: 0, reduced, body: Body::Leaf(units) })
+}
+
+fn branch<R: ReactionRule>(left: Box<Blocks>, right: Box<Blocks>, rule: &R) -> Box<Blocks> {
+    Box::new(Blocks {
+        len: left.len + right.len,
+        height: 1 + left.height.max(right.height),
+        reduced: left.reduced.join(&right.reduced, rule),
+        body: Body::Branch(left, right),
+    })
+}
+
+fn into_children(node: Blocks) -> (Box<Blocks>, Box<Blocks>) {
+    match node.body {
+        Body::Branch(left, right) => (left, right),
+        Body::Leaf(_) => unreachable!("Only branches are taller than another node"),
+    }
+}
+
+/// Joins two balanced trees whose heights differ by at most two, rotating once if needed.
+fn balance<R: ReactionRule>(left: Box<Blocks>, right: Box<Blocks>, rule: &R) -> Box<Blocks> {
+    if left.height > right.height + 1 {
+        let (outer, inner) = into_children(*left);
+        if outer.height >= inner.height {
+            branch(outer, branch(inner, right, rule), rule)
+        } else {
+            let (inner_left, inner_right) = into_children(*inner);
+            branch(branch(outer, inner_left, rule), branch(inner_right, right, rule), rule)
+        }
+    } else if right.height > left.height + 1 {
+        let (inner, outer) = into_children(*right);
+        if outer.height >= inner.height {
+            branch(branch(left, inner, rule), outer, rule)
+        } else {
+            let (inner_left, inner_right) = into_children(*inner);
+            branch(branch(left, inner_left, rule), branch(inner_right, outer, rule), rule)
+        }
+    } else {
+        branch(left, right, rule)
+    }
+}
+
+fn build<R: ReactionRule>(blocks: &[&[u8]], rule: &R) -> Option<Box<Blocks>> {
+    match blocks.len() {
+        0 => None,
+        1 => Some(leaf(blocks[0].to_vec(), rule)),
+        n => {
+            let (left, right) = blocks.split_at(n / 2);
+            Some(branch(build(left, rule)?, build(right, rule)?, rule))
+        }
+    }
+}
+
+/// Applies `edit` to the block holding `position`, with the offset within it, then splits or
+/// drops that block as needed and rebalances on the way back up. Returns `None` once the last
+/// block is dropped.
+fn edit<T, R, F>(node: Blocks, position: usize, rule: &R, edit_block: F) -> (Option<Box<Blocks>>, T)
+where
+    R: ReactionRule,
+    F: FnOnce(&mut Vec<u8>, usize) -> T,
+{
+    match node.body {
+        Body::Leaf(mut units) => {
+            let result = edit_block(&mut units, position);
+            let node = if units.is_empty() {
+                None
+            } else if units.len() > 2 * BLOCK_SIZE {
+                let tail = units.split_off(BLOCK_SIZE);
+                Some(branch(leaf(units, rule), leaf(tail, rule), rule))
+            } else {
+                Some(leaf(units, rule))
+            };
+
+            (node, result)
+        }
+        Body::Branch(left, right) => {
+            if position < left.len {
+                let (left, result) = edit(*left, position, rule, edit_block);
+                match left {
+                    Some(left) => (Some(balance(left, right, rule)), result),
+                    None => (Some(right), result),
+                }
+            } else {
+                let (right, result) = edit(*right, position - left.len, rule, edit_block);
+                match right {
+                    Some(right) => (Some(balance(left, right, rule)), result),
+                    None => (Some(left), result),
+                }
+            }
+        }
+    }
+}
+
+fn collect_units(node: &Blocks, units: &mut Vec<u8>) {
+    match node.body {
+        Body::Leaf(ref block) => units.extend_from_slice(block),
+        Body::Branch(ref left, ref right) => {
+            collect_units(left, units);
+            collect_units(right, units);
+        }
+    }
+}
+
+/// An editable polymer. Positions count units from zero, ignoring whitespace in the original.
+#[derive(Debug, Clone)]
+pub struct Polymer<R = CasePair> {
+    root: Option<Box<Blocks>>,
+    rule: R,
+}
+
+impl Polymer<CasePair> {
+    pub fn new(polymer: &str) -> Polymer<CasePair> {
+        Polymer::with_rule(polymer, CasePair)
+    }
+}
+
+impl<R: ReactionRule> Polymer<R> {
+    pub fn with_rule(polymer: &str, rule: R) -> Polymer<R> {
+        let units = units_of(polymer);
+        let blocks: Vec<&[u8]> = units.chunks(BLOCK_SIZE).collect();
+        let root = build(&blocks, &rule);
+
+        Polymer { root, rule }
+    }
+
+    /// The number of units before collapsing.
+    pub fn len(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn edit<T, F: FnOnce(&mut Vec<u8>, usize) -> T>(&mut self, position: usize, edit_block: F) -> T {
+        let root = self.root.take().expect("Only non-empty polymers are edited");
+        let (root, result) = edit(*root, position, &self.rule, edit_block);
+        self.root = root;
+
+        result
+    }
+
+    fn check_unit(unit: char) -> u8 {
+        assert!(unit.is_ascii() && !unit.is_ascii_whitespace(), "Invalid polymer unit {:?}", unit);
+        unit as u8
+    }
+
+    /// Inserts `unit` before `position`.
+    ///
+    /// Panics if `position > len` or the unit is not a non-whitespace ASCII character.
+    pub fn insert(&mut self, position: usize, unit: char) {
+        assert!(position <= self.len(), "Insert position {} out of bounds for length {}", position, self.len());
+        let unit = Self::check_unit(unit);
+
+        if self.root.is_none() {
+            self.root = Some(leaf(vec![unit], &self.rule));
+        } else {
+            self.edit(position, |block, offset| block.insert(offset, unit));
+        }
+    }
+
+    /// Removes and returns the unit at `position`. Panics if `position >= len`.
+    pub fn delete(&mut self, position: usize) -> char {
+        assert!(position < self.len(), "Delete position {} out of bounds for length {}", position, self.len());
+
+        self.edit(position, |block, offset| block.remove(offset)) as char
+    }
+
+    /// Replaces the unit at `position`, returning the old one.
+    ///
+    /// Panics if `position >= len` or the unit is not a non-whitespace ASCII character.
+    pub fn replace(&mut self, position: usize, unit: char) -> char {
+        assert!(position < self.len(), "Replace position {} out of bounds for length {}", position, self.len());
+        let unit = Self::check_unit(unit);
+
+        self.edit(position, |block, offset| ::std::mem::replace(&mut block[offset], unit)) as char
+    }
+
+    /// The uncollapsed units.
+    pub fn units(&self) -> String {
+        let mut units = Vec::with_capacity(self.len());
+        if let Some(ref root) = self.root {
+            collect_units(root, &mut units);
+        }
+        to_polymer(units)
+    }
+
+    /// The polymer after all reactions have happened.
+    pub fn collapsed(&self) -> String {
+        to_polymer(self.root.as_ref().map(|root| root.reduced.to_vec()).unwrap_or_default())
+    }
+
+    /// The length of the collapsed polymer.
+    pub fn collapsed_len(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.reduced.len())
+    }
+
+    #[cfg(test)]
+    fn height(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use day5::collapse_polymer;
+    use helpers;
+
+    #[test]
+    fn should_edit_polymer() {
+        let mut polymer = Polymer::new("dabAcCaCBAcCcaDA");
+        assert_eq!(16, polymer.len());
+        assert_eq!(10, polymer.collapsed_len());
+
+        assert_eq!('d', polymer.delete(0));
+        assert_eq!('D', polymer.replace(13, 'x'));
+        polymer.insert(15, 'd');
+        assert_eq!("abAcCaCBAcCcaxAd", polymer.units());
+        assert_eq!("abCBAcaxAd", polymer.collapsed());
+
+        polymer.insert(0, 'D');
+        assert_eq!(11, polymer.collapsed_len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_out_of_bounds_edits() {
+        Polymer::new("aB").delete(2);
+    }
+
+    #[test]
+    fn should_match_collapse_after_random_edits() {
+        const UNITS: &[u8] = b"aAbBcC";
+
+        for seed in 0..5 {
+            let mut rng = helpers::test_rng(seed);
+            let start = rng.string(600, UNITS);
+            let mut next = |bound: usize| rng.below(bound as u64) as usize;
+            let mut polymer = Polymer::new(&start);
+            let mut expected: Vec<char> = start.chars().collect();
+
+            for _ in 0..1500 {
+                match next(3) {
+                    0 => {
+                        let (at, unit) = (next(expected.len() + 1), UNITS[next(UNITS.len())] as char);
+                        polymer.insert(at, unit);
+                        expected.insert(at, unit);
+                    }
+                    1 if !expected.is_empty() => {
+                        let at = next(expected.len());
+                        assert_eq!(expected.remove(at), polymer.delete(at));
+                    }
+                    _ if !expected.is_empty() => {
+                        let (at, unit) = (next(expected.len()), UNITS[next(UNITS.len())] as char);
+                        assert_eq!(expected[at], polymer.replace(at, unit));
+                        expected[at] = unit;
+                    }
+                    _ => {}
+                }
+
+                let units: String = expected.iter().collect();
+                assert_eq!(expected.len(), polymer.len());
+                assert_eq!(collapse_polymer(&units).len(), polymer.collapsed_len());
+            }
+
+            let units: String = expected.iter().collect();
+            assert_eq!(units, polymer.units());
+            assert_eq!(collapse_polymer(&units), polymer.collapsed());
+        }
+    }
+
+    #[test]
+    fn should_grow_from_empty() {
+        let mut polymer = Polymer::new("");
+        assert!(polymer.is_empty());
+
+        for i in 0..1000 {
+            polymer.insert(i, if i % 2 == 0 { 'a' } else { 'b' });
+        }
+        assert_eq!(1000, polymer.collapsed_len());
+
+        while !polymer.is_empty() {
+            polymer.delete(0);
+        }
+        assert_eq!("", polymer.collapsed());
+    }
+
+    #[test]
+    fn should_stay_balanced_while_blocks_split_and_empty() {
+        let mut polymer = Polymer::new("");
+
+        for i in 0..20_000 {
+            polymer.insert(i / 2, if i % 3 == 0 { 'x' } else { 'y' });
+        }
+        // Blocks hold at least 256 units once split, so there are at most 79, and a height-balanced
+        // tree over 79 leaves is at most 9 levels high. Deleting never adds blocks.
+        assert!(polymer.height() <= 9, "height {}", polymer.height());
+
+        for _ in 0..15_000 {
+            let middle = polymer.len() / 2;
+            polymer.delete(middle);
+        }
+        assert_eq!(5_000, polymer.collapsed_len());
+        assert!(polymer.height() <= 9, "height {}", polymer.height());
+    }
+}
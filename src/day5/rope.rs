@@ -0,0 +1,242 @@
+//! Persistent sequences of units. Ropes never change once built, so joining two of them shares
+//! the nodes of both and only allocates the few nodes along the join.
+
+use std::rc::Rc;
+
+use super::rules::ReactionRule;
+
+#[derive(Debug)]
+enum Body {
+    Leaf(Box<[u8]>),
+    Branch(Rc<Node>, Rc<Node>),
+}
+
+/// A node of a height-balanced tree whose leaves hold the units in order.
+#[derive(Debug)]
+struct Node {
+    len: usize,
+    height: usize,
+    body: Body,
+}
+
+fn branch(left: Rc<Node>, right: Rc<Node>) -> Rc<Node> {
+    Rc::new(Node { len: left.len + right.len, height: 1 + left.height.max(right.height), body: Body::Branch(left, right) })
+}
+
+fn children(node: &Node) -> (&Rc<Node>, &Rc<Node>) {
+    match node.body {
+        Body::Branch(ref left, ref right) => (left, right),
+        Body::Leaf(_) => unreachable!("Only branches are taller than another node"),
+    }
+}
+
+/// Joins two balanced trees whose heights differ by at most two, rotating once if needed.
+fn balance(left: Rc<Node>, right: Rc<Node>) -> Rc<Node> {
+    if left.height > right.height + 1 {
+        let (outer, inner) = children(&left);
+        if outer.height >= inner.height {
+            branch(outer.clone(), branch(inner.clone(), right))
+        } else {
+            let (inner_left, inner_right) = children(inner);
+            branch(branch(outer.clone(), inner_left.clone()), branch(inner_right.clone(), right))
+        }
+    } else if right.height > left.height + 1 {
+        let (inner, outer) = children(&right);
+        if outer.height >= inner.height {
+            branch(branch(left, inner.clone()), outer.clone())
+        } else {
+            let (inner_left, inner_right) = children(inner);
+            branch(branch(left, inner_left.clone()), branch(inner_right.clone(), outer.clone()))
+        }
+    } else {
+        branch(left, right)
+    }
+}
+
+/// Joins two balanced trees of any heights, descending the taller one's inner edge until the
+/// heights meet. Allocates a node per level descended.
+fn concat(left: &Rc<Node>, right: &Rc<Node>) -> Rc<Node> {
+    if left.height > right.height + 1 {
+        let (outer, inner) = children(left);
+        balance(outer.clone(), concat(inner, right))
+    } else if right.height > left.height + 1 {
+        let (inner, outer) = children(right);
+        balance(concat(left, inner), outer.clone())
+    } else {
+        branch(left.clone(), right.clone())
+    }
+}
+
+fn take(node: &Rc<Node>, count: usize) -> Rope {
+    if count == 0 {
+        return Rope::default();
+    }
+    if count >= node.len {
+        return Rope(Some(node.clone()));
+    }
+
+    match node.body {
+        Body::Leaf(ref units) => Rope::new(&units[..count]),
+        Body::Branch(ref left, _) if count <= left.len => take(left, count),
+        Body::Branch(ref left, ref right) => Rope(Some(left.clone())).concat(&take(right, count - left.len)),
+    }
+}
+
+fn skip(node: &Rc<Node>, count: usize) -> Rope {
+    if count >= node.len {
+        return Rope::default();
+    }
+    if count == 0 {
+        return Rope(Some(node.clone()));
+    }
+
+    match node.body {
+        Body::Leaf(ref units) => Rope::new(&units[count..]),
+        Body::Branch(ref left, ref right) if count >= left.len => skip(right, count - left.len),
+        Body::Branch(ref left, ref right) => skip(left, count).concat(&Rope(Some(right.clone()))),
+    }
+}
+
+/// The leaves of a rope in order, or in reverse.
+struct Leaves<'a> {
+    stack: Vec<&'a Node>,
+    reversed: bool,
+}
+
+impl<'a> Iterator for Leaves<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        while let Some(node) = self.stack.pop() {
+            match node.body {
+                Body::Leaf(ref units) => return Some(units),
+                Body::Branch(ref left, ref right) if self.reversed => {
+                    self.stack.push(left);
+                    self.stack.push(right);
+                }
+                Body::Branch(ref left, ref right) => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// An immutable sequence of units. Cloning, joining and slicing share nodes rather than copying
+/// units, and each allocates O(log n) nodes.
+#[derive(Debug, Clone, Default)]
+pub struct Rope(Option<Rc<Node>>);
+
+impl Rope {
+    pub fn new(units: &[u8]) -> Rope {
+        match units.len() {
+            0 => Rope::default(),
+            len => Rope(Some(Rc::new(Node { len, height: 0, body: Body::Leaf(units.into()) }))),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, |node| node.len)
+    }
+
+    #[cfg(test)]
+    fn height(&self) -> usize {
+        self.0.as_ref().map_or(0, |node| node.height)
+    }
+
+    /// The units of `self` followed by those of `other`.
+    pub fn concat(&self, other: &Rope) -> Rope {
+        match (&self.0, &other.0) {
+            (Some(left), Some(right)) => Rope(Some(concat(left, right))),
+            (Some(_), None) => self.clone(),
+            (None, _) => other.clone(),
+        }
+    }
+
+    /// The first `count` units.
+    pub fn take(&self, count: usize) -> Rope {
+        self.0.as_ref().map_or_else(Rope::default, |node| take(node, count))
+    }
+
+    /// All but the first `count` units.
+    pub fn skip(&self, count: usize) -> Rope {
+        self.0.as_ref().map_or_else(Rope::default, |node| skip(node, count))
+    }
+
+    fn leaves(&self, reversed: bool) -> Leaves<'_> {
+        Leaves { stack: self.0.iter().map(|node| &**node).collect(), reversed }
+    }
+
+    /// Appends `other` to this reduced rope, annihilating units across the join as
+    /// [`Segment::join`](../chunked/struct.Segment.html#method.join) does. Costs the units that
+    /// annihilate plus O(log n).
+    pub fn join<R: ReactionRule>(&self, other: &Rope, rule: &R) -> Rope {
+        let matched = self
+            .leaves(true)
+            .flat_map(|units| units.iter().rev())
+            .zip(other.leaves(false).flat_map(|units| units.iter()))
+            .take_while(|&(&left, &right)| rule.reacts(left, right))
+            .count();
+
+        self.take(self.len() - matched).concat(&other.skip(matched))
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut units = Vec::with_capacity(self.len());
+        for leaf in self.leaves(false) {
+            units.extend_from_slice(leaf);
+        }
+        units
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use day5::chunked::Segment;
+    use day5::rules::CasePair;
+    use helpers;
+
+    fn rope(units: &[u8], leaf: usize) -> Rope {
+        units.chunks(leaf).fold(Rope::default(), |rope, chunk| rope.concat(&Rope::new(chunk)))
+    }
+
+    #[test]
+    fn should_slice_and_concat() {
+        let units = b"abcdefghijklmnopqrstuvwxyz";
+        let whole = rope(units, 3);
+
+        assert_eq!(units.to_vec(), whole.to_vec());
+        for count in 0..=units.len() + 1 {
+            assert_eq!(units[..count.min(units.len())].to_vec(), whole.take(count).to_vec());
+            assert_eq!(units[count.min(units.len())..].to_vec(), whole.skip(count).to_vec());
+            assert_eq!(units.to_vec(), whole.take(count).concat(&whole.skip(count)).to_vec());
+        }
+        assert_eq!(0, Rope::new(b"").len());
+    }
+
+    #[test]
+    fn should_join_like_segments() {
+        let mut rng = helpers::test_rng(5);
+
+        for _ in 0..200 {
+            let (left, right) = (rng.string(40, b"aAbB"), rng.string(40, b"aAbB"));
+            let (left, right) = (Segment::reduce(left.as_bytes(), &CasePair), Segment::reduce(right.as_bytes(), &CasePair));
+            let joined = rope(left.as_bytes(), 4).join(&rope(right.as_bytes(), 5), &CasePair);
+
+            assert_eq!(left.join(&right, &CasePair).as_bytes(), &joined.to_vec()[..]);
+        }
+    }
+
+    #[test]
+    fn should_stay_balanced() {
+        let units: Vec<u8> = (0..20_000).map(|i| b'a' + (i % 26) as u8).collect();
+        let whole = rope(&units, 1);
+
+        assert!(whole.height() <= 21, "height {}", whole.height());
+        assert!(whole.skip(7_777).concat(&whole.take(3_333)).height() <= 21);
+    }
+}
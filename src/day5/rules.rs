@@ -0,0 +1,128 @@
+//! Which adjacent units react. The puzzle's rule is [`CasePair`](struct.CasePair.html); other
+//! cancellation systems can be described with a [`PairTable`](struct.PairTable.html) or any
+//! closure wrapped in a [`Predicate`](struct.Predicate.html).
+
+use std::fs;
+
+use error::{Error, ParseError};
+use helpers;
+
+/// Decides whether two adjacent units annihilate.
+pub trait ReactionRule {
+    /// Whether `left` immediately followed by `right` reacts.
+    fn reacts(&self, left: u8, right: u8) -> bool;
+
+    /// The type a unit belongs to, used when removing all units of a type.
+    fn unit_type(&self, unit: u8) -> u8 {
+        unit.to_ascii_lowercase()
+    }
+}
+
+/// Units of the same letter and opposite case react, e.g. `aA` or `Bb`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct CasePair;
+
+impl ReactionRule for CasePair {
+    #[inline]
+    fn reacts(&self, left: u8, right: u8) -> bool {
+        left != right && left.eq_ignore_ascii_case(&right)
+    }
+}
+
+/// An explicit, unordered set of reacting pairs.
+#[derive(Clone, Debug)]
+pub struct PairTable {
+    table: Vec<bool>,
+}
+
+impl Default for PairTable {
+    fn default() -> PairTable {
+        PairTable { table: vec![false; 256 * 256] }
+    }
+}
+
+impl PairTable {
+    /// Makes `a` and `b` react whichever order they appear in.
+    pub fn insert(&mut self, a: u8, b: u8) {
+        self.table[a as usize * 256 + b as usize] = true;
+        self.table[b as usize * 256 + a as usize] = true;
+    }
+
+    /// Parses a table with one pair per line, e.g. `aB`. Blank lines and `#` comments are ignored.
+    pub fn parse(input: &str) -> Result<PairTable, ParseError> {
+        let mut table = PairTable::default();
+
+        for pair in helpers::lines(input.as_bytes()).skip_blank().skip_comments("#").parse_with(parse_pair) {
+            let (a, b) = pair?;
+            table.insert(a, b);
+        }
+
+        Ok(table)
+    }
+
+    pub fn load(path: &str) -> Result<PairTable, Error> {
+        let contents = fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })?;
+
+        PairTable::parse(&contents).map_err(|source| Error::Rules { path: path.to_string(), source })
+    }
+}
+
+fn parse_pair(line: &str) -> Result<(u8, u8), String> {
+    let units: Vec<u8> = line.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+
+    match units.as_slice() {
+        &[a, b] if a.is_ascii_graphic() && b.is_ascii_graphic() => Ok((a, b)),
+        _ => Err("expected a pair of units like 'aB'".to_string()),
+    }
+}
+
+impl ReactionRule for PairTable {
+    #[inline]
+    fn reacts(&self, left: u8, right: u8) -> bool {
+        self.table[left as usize * 256 + right as usize]
+    }
+}
+
+/// Reacts whenever the wrapped closure returns true.
+pub struct Predicate<F>(pub F);
+
+impl<F: Fn(u8, u8) -> bool> ReactionRule for Predicate<F> {
+    fn reacts(&self, left: u8, right: u8) -> bool {
+        (self.0)(left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_pairs_react() {
+        assert!(CasePair.reacts(b'a', b'A'));
+        assert!(CasePair.reacts(b'B', b'b'));
+        assert!(!CasePair.reacts(b'a', b'a'));
+        assert!(!CasePair.reacts(b'a', b'B'));
+        assert_eq!(b'q', CasePair.unit_type(b'Q'));
+    }
+
+    #[test]
+    fn can_parse_pair_table() {
+        let table = PairTable::parse("# pairs\nab\n\nc D\n").unwrap();
+
+        assert!(table.reacts(b'a', b'b'));
+        assert!(table.reacts(b'b', b'a'));
+        assert!(table.reacts(b'D', b'c'));
+        assert!(!table.reacts(b'a', b'A'));
+
+        assert_eq!(3, PairTable::parse("ab\ncd\nabc\n").unwrap_err().line);
+        assert!(PairTable::load("resources/not-a-file.nope").is_err());
+    }
+
+    #[test]
+    fn predicates_react() {
+        let rule = Predicate(|a: u8, b: u8| a + 1 == b);
+
+        assert!(rule.reacts(b'a', b'b'));
+        assert!(!rule.reacts(b'b', b'a'));
+    }
+}
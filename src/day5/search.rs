@@ -0,0 +1,232 @@
+//! Searching for the set of unit types whose removal leaves the shortest polymer.
+
+use super::rules::{CasePair, ReactionRule};
+use super::{collapse_units, collapse_without, unit_types, units_of};
+
+/// Above this many candidate sets, `Strategy::Auto` switches from exhaustive to greedy search.
+pub const EXHAUSTIVE_LIMIT: u64 = 10_000;
+
+/// How candidate sets of unit types are chosen.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Strategy {
+    /// Every set of up to `k` unit types still present in the polymer. Sets that would remove a
+    /// unit type no longer present are skipped, as would branches that have collapsed to nothing.
+    Exhaustive,
+    /// Removes one unit type at a time, each time keeping whichever removal is shortest.
+    Greedy,
+    /// Exhaustive if there are at most `EXHAUSTIVE_LIMIT` candidate sets, otherwise greedy.
+    Auto,
+}
+
+/// The collapsed polymer length after removing each evaluated set of unit types, in evaluation
+/// order. Sets are written as their unit types in order, e.g. `"ac"`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SetRemovalReport {
+    pub lengths: Vec<(String, usize)>,
+}
+
+impl SetRemovalReport {
+    /// The set leaving the shortest polymer, preferring smaller then alphabetically earlier sets
+    /// on ties.
+    pub fn best(&self) -> Option<(String, usize)> {
+        self.lengths.iter().min_by_key(|&(set, len)| (*len, set.len(), set.clone())).cloned()
+    }
+}
+
+struct Search<'a, R: 'a> {
+    rule: &'a R,
+    /// Whether a collapsed polymer can stand in for the original, as it can for `CasePair`.
+    shortcut: bool,
+    lengths: Vec<(String, usize)>,
+}
+
+impl<'a, R: ReactionRule> Search<'a, R> {
+    /// Removes `unit` from `units`, recording the collapsed length under `set`. Returns the units
+    /// later removals should start from, and the collapsed length.
+    fn remove(&mut self, units: &[u8], unit: u8, set: &str) -> (Vec<u8>, usize) {
+        let collapsed = collapse_without(units, unit, self.rule);
+        let len = collapsed.len();
+        self.lengths.push((set.to_string(), len));
+
+        if self.shortcut {
+            (collapsed, len)
+        } else {
+            (units.iter().cloned().filter(|&u| self.rule.unit_type(u) != unit).collect(), len)
+        }
+    }
+
+    fn exhaustive(&mut self, units: &[u8], set: &mut String, k: usize) {
+        if k == 0 {
+            return;
+        }
+
+        let after = set.bytes().last();
+        for unit in unit_types(units, self.rule).into_iter().filter(|&u| after.iter().all(|&last| u > last)) {
+            set.push(unit as char);
+            let (remaining, len) = self.remove(units, unit, set);
+            if len > 0 {
+                self.exhaustive(&remaining, set, k - 1);
+            }
+            set.pop();
+        }
+    }
+
+    fn greedy(&mut self, units: &[u8], k: usize) {
+        let mut units = units.to_vec();
+        let mut set = String::new();
+
+        for _ in 0..k {
+            let mut best: Option<(usize, u8, Vec<u8>)> = None;
+
+            for unit in unit_types(&units, self.rule) {
+                let mut candidate = set.clone();
+                candidate.push(unit as char);
+                let (remaining, len) = self.remove(&units, unit, &sorted(&candidate));
+
+                if best.iter().all(|&(best_len, _, _)| len < best_len) {
+                    best = Some((len, unit, remaining));
+                }
+            }
+
+            match best {
+                Some((len, unit, remaining)) => {
+                    set.push(unit as char);
+                    units = remaining;
+                    if len == 0 {
+                        return;
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+fn sorted(set: &str) -> String {
+    let mut units: Vec<char> = set.chars().collect();
+    units.sort();
+    units.into_iter().collect()
+}
+
+/// The number of non-empty sets of at most `k` of `n` unit types, saturating.
+fn candidate_sets(n: usize, k: usize) -> u64 {
+    let mut total: u64 = 0;
+    let mut choose: u64 = 1;
+
+    for i in 1..=k.min(n) {
+        choose = choose.saturating_mul((n - i + 1) as u64) / i as u64;
+        total = total.saturating_add(choose);
+    }
+
+    total
+}
+
+fn search<R: ReactionRule>(units: &[u8], k: usize, strategy: Strategy, rule: &R, shortcut: bool) -> SetRemovalReport {
+    let strategy = match strategy {
+        Strategy::Auto if candidate_sets(unit_types(units, rule).len(), k) <= EXHAUSTIVE_LIMIT => Strategy::Exhaustive,
+        Strategy::Auto => Strategy::Greedy,
+        s => s,
+    };
+
+    let mut search = Search { rule, shortcut, lengths: Vec::new() };
+    match strategy {
+        Strategy::Greedy => search.greedy(units, k),
+        _ => search.exhaustive(units, &mut String::new(), k),
+    }
+
+    SetRemovalReport { lengths: search.lengths }
+}
+
+/// Evaluates removing sets of up to `k` unit types from `polymer`, chosen by `strategy`. Each
+/// larger set starts from the collapsed polymer left by the set it extends.
+pub fn set_removal_report(polymer: &str, k: usize, strategy: Strategy) -> SetRemovalReport {
+    search(&collapse_units(polymer.bytes(), &CasePair), k, strategy, &CasePair, true)
+}
+
+/// As `set_removal_report`, with unit types and reactions defined by `rule`. Each set is
+/// collapsed from the full polymer with that set removed.
+pub fn set_removal_report_with_rule<R: ReactionRule>(polymer: &str, k: usize, strategy: Strategy, rule: &R) -> SetRemovalReport {
+    search(&units_of(polymer), k, strategy, rule, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use day5::{collapse_polymer, unit_removal_report, Predicate};
+
+    fn collapse_removing(polymer: &str, set: &str) -> usize {
+        let remaining: String = polymer.chars().filter(|c| !set.contains(c.to_ascii_lowercase())).collect();
+        collapse_polymer(&remaining).len()
+    }
+
+    #[test]
+    fn should_match_single_unit_removal() {
+        let polymer = "dabAcCaCBAcCcaDA";
+        let report = set_removal_report(polymer, 1, Strategy::Exhaustive);
+        let single: Vec<(String, usize)> = unit_removal_report(polymer, 1)
+            .lengths
+            .into_iter()
+            .map(|(unit, len)| (unit.to_string(), len))
+            .collect();
+
+        assert_eq!(single, report.lengths);
+        assert_eq!(Some(("c".to_string(), 4)), report.best());
+    }
+
+    #[test]
+    fn should_search_every_set() {
+        let polymer = "dabAcCaCBAcCcaDA";
+        let report = set_removal_report(polymer, 2, Strategy::Exhaustive);
+        let sets: Vec<&str> = report.lengths.iter().map(|(set, _)| set.as_str()).collect();
+
+        assert_eq!(vec!["a", "ab", "ac", "ad", "b", "bc", "bd", "c", "cd", "d"], sets);
+        for (set, len) in report.lengths.iter() {
+            assert_eq!(collapse_removing(polymer, set), *len, "removing {}", set);
+        }
+        assert_eq!(Some(("ab".to_string(), 0)), report.best());
+    }
+
+    #[test]
+    fn should_prune_collapsed_branches() {
+        let report = set_removal_report("abBAcCd", 3, Strategy::Exhaustive);
+
+        assert_eq!(Some(("d".to_string(), 0)), report.best());
+        assert_eq!(vec![("d".to_string(), 0)], report.lengths);
+    }
+
+    #[test]
+    fn should_search_greedily() {
+        let polymer = "dabAcCaCBAcCcaDA";
+        let report = set_removal_report(polymer, 3, Strategy::Greedy);
+
+        // Removing c then a collapses everything, so the third step is never taken.
+
+        assert_eq!(4 + 2, report.lengths.len());
+        assert_eq!(Some(("ac".to_string(), 0)), report.best());
+        for (set, len) in report.lengths.iter() {
+            assert_eq!(collapse_removing(polymer, set), *len, "removing {}", set);
+        }
+    }
+
+    #[test]
+    fn should_choose_strategy() {
+        assert_eq!(4 + 6, candidate_sets(4, 2));
+        assert_eq!(26, candidate_sets(26, 1));
+        assert_eq!(
+            set_removal_report("dabAcCaCBAcCcaDA", 2, Strategy::Exhaustive),
+            set_removal_report("dabAcCaCBAcCcaDA", 2, Strategy::Auto)
+        );
+        assert!(candidate_sets(26, 13) > EXHAUSTIVE_LIMIT);
+    }
+
+    #[test]
+    fn should_search_with_rules() {
+        let rule = Predicate(|a: u8, b: u8| a != b && a.eq_ignore_ascii_case(&b));
+        let polymer = "dabAcCaCBAcCcaDA";
+
+        assert_eq!(
+            set_removal_report(polymer, 2, Strategy::Exhaustive),
+            set_removal_report_with_rule(polymer, 2, Strategy::Exhaustive, &rule)
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Errors shared across the crate.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+/// A line of input that could not be parsed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    /// One-based line number within the input.
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, text: &str, reason: &str) -> ParseError {
+        ParseError { line, text: text.to_string(), reason: reason.to_string() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {} in '{}'", self.line, self.reason, self.text)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// A part that cannot be answered, even though its input parsed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SolveError {
+    pub reason: String,
+}
+
+impl SolveError {
+    pub fn new<T: fmt::Display>(reason: T) -> SolveError {
+        SolveError { reason: reason.to_string() }
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl error::Error for SolveError {}
+
+#[derive(Debug)]
+pub enum Error {
+    Io { path: String, source: io::Error },
+    Parse { day: u8, source: ParseError },
+    Solve { day: u8, part: u8, source: SolveError },
+    Answers { path: String, source: ParseError },
+    Rules { path: String, source: ParseError },
+    UnknownDay(u8),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io { ref path, ref source } => write!(f, "Failed to load file {}: {}", path, source),
+            Error::Parse { day, ref source } => write!(f, "Failed to parse input for day {}, {}", day, source),
+            Error::Solve { day, part, ref source } => write!(f, "Failed to solve day {} part {}, {}", day, part, source),
+            Error::Answers { ref path, ref source } => write!(f, "Failed to parse answers file {}, {}", path, source),
+            Error::Rules { ref path, ref source } => write!(f, "Failed to parse reaction rules file {}, {}", path, source),
+            Error::UnknownDay(day) => write!(f, "Input was not a valid day '{}'", day),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io { ref source, .. } => Some(source),
+            Error::Parse { ref source, .. } => Some(source),
+            Error::Solve { ref source, .. } => Some(source),
+            Error::Answers { ref source, .. } => Some(source),
+            Error::Rules { ref source, .. } => Some(source),
+            Error::UnknownDay(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_describe_errors() {
+        assert_eq!(
+            "line 3: invalid digit found in string in '+1x'",
+            ParseError::new(3, "+1x", "invalid digit found in string").to_string()
+        );
+
+        assert_eq!(
+            "Failed to parse input for day 1, line 3: bad in 'x'",
+            Error::Parse { day: 1, source: ParseError::new(3, "x", "bad") }.to_string()
+        );
+
+        assert_eq!(
+            "Failed to solve day 1 part 2, overflowed",
+            Error::Solve { day: 1, part: 2, source: SolveError::new("overflowed") }.to_string()
+        );
+
+        assert_eq!("Input was not a valid day '26'", Error::UnknownDay(26).to_string());
+    }
+}
@@ -1,9 +1,165 @@
-use std::io::Error;
+//! Shared input handling.
+//!
+//! [`lines`](fn.lines.html) streams numbered lines from any reader and can parse them straight
+//! into a `FromStr` type:
+//!
+//! ```
+//! use advent_of_code_2018::helpers;
+//!
+//! let input = "# changes\n+1\n\n-2\n";
+//! let changes: Result<Vec<i32>, _> = helpers::lines(input.as_bytes())
+//!     .skip_blank()
+//!     .skip_comments("#")
+//!     .parse()
+//!     .collect();
+//!
+//! assert_eq!(Ok(vec![1, -2]), changes);
+//! ```
+
+use std::fmt::Display;
 use std::fs;
+use std::io::{self, BufRead, BufReader, Error, Read};
+use std::str::FromStr;
+
+use error::ParseError;
 
-pub fn read_lines<'a>(filename: &str) -> Result<Vec<String>, Error> {
+/// Reads a file and returns its lines without line endings.
+pub fn read_lines(filename: &str) -> Result<Vec<String>, Error> {
     let lines = fs::read_to_string(filename)?;
-    return Ok(lines.lines().map(|x| x.to_string()).collect());
+    Ok(split_lines(&lines))
+}
+
+/// Splits already loaded input into owned lines.
+pub fn split_lines(input: &str) -> Vec<String> {
+    input.lines().map(|x| x.to_string()).collect()
+}
+
+/// A line of input with its one-based line number.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Line {
+    pub number: usize,
+    pub text: String,
+}
+
+/// Lazily reads numbered lines from a reader. Created by [`lines`](fn.lines.html).
+pub struct Lines<R> {
+    inner: io::Lines<BufReader<R>>,
+    number: usize,
+    skip_blank: bool,
+    comment: Option<String>,
+}
+
+/// Streams lines from `reader` without loading it all into memory.
+pub fn lines<R: Read>(reader: R) -> Lines<R> {
+    Lines { inner: BufReader::new(reader).lines(), number: 0, skip_blank: false, comment: None }
+}
+
+impl<R: Read> Lines<R> {
+    /// Skips lines that are empty or only whitespace.
+    pub fn skip_blank(mut self) -> Lines<R> {
+        self.skip_blank = true;
+        self
+    }
+
+    /// Skips lines starting with `prefix`, ignoring leading whitespace.
+    pub fn skip_comments(mut self, prefix: &str) -> Lines<R> {
+        self.comment = Some(prefix.to_string());
+        self
+    }
+
+    /// Parses each line, with surrounding whitespace trimmed, into `T`.
+    pub fn parse<T>(self) -> impl Iterator<Item=Result<T, ParseError>>
+        where T: FromStr, T::Err: Display
+    {
+        self.parse_with(|text| text.trim().parse::<T>().map_err(|e| e.to_string()))
+    }
+
+    /// Parses each line with `parse`, reporting failures with the line number and text.
+    pub fn parse_with<T, F>(self, parse: F) -> impl Iterator<Item=Result<T, ParseError>>
+        where F: Fn(&str) -> Result<T, String>
+    {
+        self.map(move |line| {
+            let line = line?;
+            parse(&line.text).map_err(|reason| ParseError::new(line.number, &line.text, &reason))
+        })
+    }
+
+    /// Groups consecutive non-blank lines into records separated by blank lines.
+    pub fn groups(mut self) -> Groups<R> {
+        self.skip_blank = false;
+        Groups { lines: self }
+    }
+
+    fn is_skipped(&self, text: &str) -> bool {
+        let trimmed = text.trim();
+
+        (self.skip_blank && trimmed.is_empty())
+            || self.comment.iter().any(|prefix| trimmed.starts_with(prefix.as_str()))
+    }
+}
+
+impl<R: Read> Iterator for Lines<R> {
+    type Item = Result<Line, ParseError>;
+
+    fn next(&mut self) -> Option<Result<Line, ParseError>> {
+        loop {
+            self.number += 1;
+
+            match self.inner.next()? {
+                Err(e) => return Some(Err(ParseError::new(self.number, "", &e.to_string()))),
+                Ok(ref text) if self.is_skipped(text) => continue,
+                Ok(text) => return Some(Ok(Line { number: self.number, text })),
+            }
+        }
+    }
+}
+
+/// Blank-line separated groups of lines. Created by [`Lines::groups`](struct.Lines.html#method.groups).
+pub struct Groups<R> {
+    lines: Lines<R>,
+}
+
+impl<R: Read> Iterator for Groups<R> {
+    type Item = Result<Vec<Line>, ParseError>;
+
+    fn next(&mut self) -> Option<Result<Vec<Line>, ParseError>> {
+        let mut group = Vec::new();
+
+        for line in self.lines.by_ref() {
+            match line {
+                Err(e) => return Some(Err(e)),
+                Ok(ref line) if line.text.trim().is_empty() && group.is_empty() => continue,
+                Ok(ref line) if line.text.trim().is_empty() => return Some(Ok(group)),
+                Ok(line) => group.push(line),
+            }
+        }
+
+        if group.is_empty() { None } else { Some(Ok(group)) }
+    }
+}
+
+/// A small deterministic generator for the randomised tests that compare fast paths against
+/// brute force.
+#[cfg(test)]
+pub struct TestRng(u64);
+
+#[cfg(test)]
+pub fn test_rng(seed: u64) -> TestRng {
+    TestRng(seed)
+}
+
+#[cfg(test)]
+impl TestRng {
+    /// A number below `bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) % bound
+    }
+
+    /// A string of `len` units, each picked from `units`.
+    pub fn string(&mut self, len: usize, units: &[u8]) -> String {
+        (0..len).map(|_| units[self.below(units.len() as u64) as usize] as char).collect()
+    }
 }
 
 #[cfg(test)]
@@ -20,6 +176,57 @@ mod tests {
         assert_eq!(vec!["+1", "-2", "+3", "+1"], lines)
     }
 
+    fn texts(lines: Vec<Line>) -> Vec<String> {
+        lines.into_iter().map(|l| l.text).collect()
+    }
+
+    #[test]
+    fn should_stream_numbered_lines() {
+        let read: Vec<Line> = lines("a\n\n# note\nb\r\n".as_bytes()).map(|l| l.unwrap()).collect();
+        assert_eq!(
+            vec![
+                Line { number: 1, text: "a".to_string() },
+                Line { number: 2, text: "".to_string() },
+                Line { number: 3, text: "# note".to_string() },
+                Line { number: 4, text: "b".to_string() },
+            ],
+            read
+        );
+
+        let read: Vec<Line> = lines("a\n\n  # note\nb\n".as_bytes())
+            .skip_blank()
+            .skip_comments("#")
+            .map(|l| l.unwrap())
+            .collect();
+        assert_eq!(vec![Line { number: 1, text: "a".to_string() }, Line { number: 4, text: "b".to_string() }], read);
+    }
+
+    #[test]
+    fn should_parse_streamed_lines() {
+        let parsed: Result<Vec<i32>, ParseError> = lines(" +1\n\n-2 \n".as_bytes()).skip_blank().parse().collect();
+        assert_eq!(Ok(vec![1, -2]), parsed);
+
+        let parsed: Result<Vec<i32>, ParseError> = lines("+1\n# c\nx\n".as_bytes()).skip_comments("#").parse().collect();
+        assert_eq!(Err(ParseError::new(3, "x", "invalid digit found in string")), parsed);
+
+        let parsed: Result<Vec<usize>, ParseError> = lines("ab\nabc\n".as_bytes())
+            .parse_with(|text| if text.len() < 3 { Ok(text.len()) } else { Err("too long".to_string()) })
+            .collect();
+        assert_eq!(Err(ParseError::new(2, "abc", "too long")), parsed);
+    }
+
+    #[test]
+    fn should_group_lines() {
+        let groups: Vec<Vec<String>> = lines("\na\nb\n\n\n# skip\nc\n\nd".as_bytes())
+            .skip_comments("#")
+            .groups()
+            .map(|g| texts(g.unwrap()))
+            .collect();
+
+        assert_eq!(vec![vec!["a", "b"], vec!["c"], vec!["d"]], groups);
+        assert_eq!(7, lines("a\n\n\n\n\n\nb".as_bytes()).groups().nth(1).unwrap().unwrap()[0].number);
+    }
+
     #[test]
     fn should_error_on_missing_file() {
         let res = read_lines("resources/not-a-file.nope");
@@ -0,0 +1,136 @@
+//! Locating puzzle inputs. By default day N reads `resources/dayN.txt`; a profile reads
+//! `resources/<profile>/dayN.txt` so several accounts' inputs can live side by side.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use error::Error;
+
+pub const DEFAULT_DIR: &str = "resources";
+
+/// Where a day's input is read from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Source {
+    Stdin,
+    File(String),
+}
+
+impl Source {
+    /// Interprets a command-line path, where `-` means standard input.
+    pub fn from_arg(arg: &str) -> Source {
+        if arg == "-" { Source::Stdin } else { Source::File(arg.to_string()) }
+    }
+
+    pub fn read(&self) -> Result<String, Error> {
+        match *self {
+            Source::Stdin => {
+                let mut contents = String::new();
+                io::stdin()
+                    .read_to_string(&mut contents)
+                    .map(|_| contents)
+                    .map_err(|source| Error::Io { path: self.to_string(), source })
+            }
+            Source::File(ref path) => fs::read_to_string(path).map_err(|source| Error::Io { path: path.clone(), source }),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::Stdin => write!(f, "-"),
+            Source::File(ref path) => write!(f, "{}", path),
+        }
+    }
+}
+
+/// A resolved input, tagged with the profile it belongs to if any.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Input {
+    pub profile: Option<String>,
+    pub source: Source,
+}
+
+/// How to find inputs: an explicit override, otherwise one file per profile under `dir`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Inputs {
+    pub dir: String,
+    pub profiles: Vec<String>,
+    pub input: Option<Source>,
+}
+
+impl Default for Inputs {
+    fn default() -> Inputs {
+        Inputs { dir: DEFAULT_DIR.to_string(), profiles: Vec::new(), input: None }
+    }
+}
+
+impl Inputs {
+    /// The inputs to run `day` against, one per profile.
+    pub fn resolve(&self, day: u8) -> Vec<Input> {
+        if let Some(ref source) = self.input {
+            return vec![Input { profile: None, source: source.clone() }];
+        }
+
+        let file = format!("day{}.txt", day);
+        let dir = Path::new(&self.dir);
+
+        if self.profiles.is_empty() {
+            return vec![Input { profile: None, source: Source::File(dir.join(&file).to_string_lossy().into_owned()) }];
+        }
+
+        self.profiles
+            .iter()
+            .map(|profile| Input {
+                profile: Some(profile.clone()),
+                source: Source::File(dir.join(profile).join(&file).to_string_lossy().into_owned()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str) -> Source {
+        Source::File(path.to_string())
+    }
+
+    #[test]
+    fn should_resolve_default_input() {
+        assert_eq!(
+            vec![Input { profile: None, source: file("resources/day3.txt") }],
+            Inputs::default().resolve(3)
+        );
+    }
+
+    #[test]
+    fn should_resolve_profiles() {
+        let inputs = Inputs { dir: "inputs".to_string(), profiles: vec!["alice".to_string(), "bob".to_string()], input: None };
+
+        assert_eq!(
+            vec![
+                Input { profile: Some("alice".to_string()), source: file("inputs/alice/day5.txt") },
+                Input { profile: Some("bob".to_string()), source: file("inputs/bob/day5.txt") },
+            ],
+            inputs.resolve(5)
+        );
+    }
+
+    #[test]
+    fn should_prefer_explicit_input() {
+        let inputs = Inputs { dir: "inputs".to_string(), profiles: Vec::new(), input: Some(Source::from_arg("-")) };
+
+        assert_eq!(vec![Input { profile: None, source: Source::Stdin }], inputs.resolve(1));
+        assert_eq!(file("my.txt"), Source::from_arg("my.txt"));
+    }
+
+    #[test]
+    fn should_read_files() {
+        assert_eq!("+1\n-2\n+3\n+1", file("resources/test-helpers.txt").read().unwrap().trim_end());
+        assert!(file("resources/not-a-file.nope").read().is_err());
+    }
+}
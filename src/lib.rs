@@ -0,0 +1,29 @@
+//! Solutions to [Advent of Code 2018](https://adventofcode.com/2018).
+//!
+//! Each day lives in its own module and exposes its parsing and solving functions, along with a
+//! [`Solution`](solution/trait.Solution.html) implementation that is listed in the
+//! [`registry`](registry/index.html). The `aoc2018` binary is a thin wrapper around
+//! [`runner`](runner/index.html).
+
+extern crate itertools;
+#[macro_use] extern crate lazy_static;
+extern crate regex;
+extern crate chrono;
+#[cfg(feature = "bigint")] extern crate num_bigint;
+#[cfg(feature = "bigint")] extern crate num_traits;
+
+pub mod answers;
+pub mod bench;
+pub mod error;
+pub mod output;
+pub mod helpers;
+pub mod inputs;
+pub mod cli;
+pub mod solution;
+pub mod registry;
+pub mod runner;
+pub mod day1;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
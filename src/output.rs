@@ -0,0 +1,185 @@
+//! Structured output of run results for consumption by other tools.
+
+use std::fmt;
+use std::str::FromStr;
+
+use bench::millis;
+use solution::DayResult;
+
+/// How the runner prints its results.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("Unknown format '{}', expected text, json or csv", s)),
+        }
+    }
+}
+
+/// One part's answer, flattened for serialisation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub day: u8,
+    pub part: u8,
+    pub label: String,
+    pub answer: String,
+    pub duration_ms: f64,
+    pub input: String,
+}
+
+impl Record {
+    /// Flattens a day's results into one record per answered part.
+    pub fn from_result(result: &DayResult, input: &str) -> Vec<Record> {
+        result.parts
+            .iter()
+            .filter_map(|p| p.answer.as_ref().ok().map(|answer| (p, answer)))
+            .map(|(p, answer)| Record {
+                day: result.day,
+                part: p.part,
+                label: answer.label.to_string(),
+                answer: answer.value.clone(),
+                duration_ms: millis(p.duration),
+                input: input.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} ({:.3}ms)", self.label, self.answer, self.duration_ms)
+    }
+}
+
+/// Quotes a string as a JSON string literal.
+pub fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_json(records: &[Record]) -> String {
+    let objects: Vec<String> = records
+        .iter()
+        .map(|r| format!(
+            "{{\"day\":{},\"part\":{},\"label\":{},\"answer\":{},\"duration_ms\":{:.6},\"input\":{}}}",
+            r.day, r.part, json_string(&r.label), json_string(&r.answer), r.duration_ms, json_string(&r.input)
+        ))
+        .collect();
+
+    format!("[{}]\n", objects.join(",\n"))
+}
+
+fn to_csv(records: &[Record]) -> String {
+    let mut out = String::from("day,part,label,answer,duration_ms,input\n");
+
+    for r in records {
+        out.push_str(&format!(
+            "{},{},{},{},{:.6},{}\n",
+            r.day, r.part, csv_field(&r.label), csv_field(&r.answer), r.duration_ms, csv_field(&r.input)
+        ));
+    }
+
+    out
+}
+
+fn to_text(records: &[Record]) -> String {
+    records.iter().map(|r| format!("{}\n", r)).collect()
+}
+
+/// Renders records in the requested format.
+pub fn render(records: &[Record], format: Format) -> String {
+    match format {
+        Format::Text => to_text(records),
+        Format::Json => to_json(records),
+        Format::Csv => to_csv(records),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record { day: 1, part: 1, label: "Resulting frequency".to_string(), answer: "556".to_string(), duration_ms: 0.5, input: "resources/day1.txt".to_string() },
+            Record { day: 2, part: 2, label: "Common, letters".to_string(), answer: "a\"b".to_string(), duration_ms: 1.25, input: "in.txt".to_string() },
+        ]
+    }
+
+    #[test]
+    fn can_parse_format() {
+        assert_eq!(Ok(Format::Json), "json".parse());
+        assert_eq!(Ok(Format::Csv), "csv".parse());
+        assert_eq!(Ok(Format::Text), "text".parse());
+        assert!("xml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn should_escape_values() {
+        assert_eq!("\"a\\\"b\\\\c\\n\\u0001\"", json_string("a\"b\\c\n\u{1}"));
+        assert_eq!("plain", csv_field("plain"));
+        assert_eq!("\"a,\"\"b\"\"\"", csv_field("a,\"b\""));
+    }
+
+    #[test]
+    fn should_render_json() {
+        assert_eq!(
+            "[{\"day\":1,\"part\":1,\"label\":\"Resulting frequency\",\"answer\":\"556\",\"duration_ms\":0.500000,\"input\":\"resources/day1.txt\"},\n\
+            {\"day\":2,\"part\":2,\"label\":\"Common, letters\",\"answer\":\"a\\\"b\",\"duration_ms\":1.250000,\"input\":\"in.txt\"}]\n",
+            render(&records(), Format::Json)
+        );
+        assert_eq!("[]\n", render(&[], Format::Json));
+    }
+
+    #[test]
+    fn should_render_csv() {
+        assert_eq!(
+            "day,part,label,answer,duration_ms,input\n\
+            1,1,Resulting frequency,556,0.500000,resources/day1.txt\n\
+            2,2,\"Common, letters\",\"a\"\"b\",1.250000,in.txt\n",
+            render(&records(), Format::Csv)
+        );
+    }
+
+    #[test]
+    fn should_render_text() {
+        assert_eq!(
+            "Resulting frequency: 556 (0.500ms)\nCommon, letters: a\"b (1.250ms)\n",
+            render(&records(), Format::Text)
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! The list of implemented days.
+
+use solution::Day;
+use day1::Day1;
+use day2::Day2;
+use day3::Day3;
+use day4::Day4;
+use day5::Day5;
+
+/// All implemented days in ascending order.
+pub fn days() -> Vec<Box<dyn Day>> {
+    vec![
+        Box::new(Day1),
+        Box::new(Day2),
+        Box::new(Day3),
+        Box::new(Day4),
+        Box::new(Day5),
+    ]
+}
+
+/// Looks up a day by number.
+pub fn find(day: u8) -> Option<Box<dyn Day>> {
+    days().into_iter().find(|d| d.day() == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_registered_in_order() {
+        let numbers: Vec<u8> = days().iter().map(|d| d.day()).collect();
+        assert_eq!(vec![1, 2, 3, 4, 5], numbers);
+    }
+
+    #[test]
+    fn can_find_day() {
+        assert_eq!(Some(3), find(3).map(|d| d.day()));
+        assert!(find(25).is_none());
+    }
+}
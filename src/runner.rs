@@ -0,0 +1,426 @@
+//! Runs registered days against their inputs and prints, verifies or benchmarks the answers.
+
+use std::fs;
+
+use answers::{self, Answers, Status};
+use bench::{self, millis, Stats};
+use cli::{AnalyseOptions, BenchOptions, ClusterFormat, ClusterOptions, CoverageOptions, Days, RunOptions, Table, TraceOptions, VerifyOptions};
+use day1::{self, Day1};
+use day2::{self, Day2};
+use day3::{self, Day3};
+use day5::{self, Day5, PairTable};
+use error::Error;
+use inputs::{Inputs, Source};
+use output::{self, Format, Record};
+use registry;
+use solution::{Day, DayResult, Solution};
+
+/// Runs `f` against each input `inputs` resolves `day` to, headed by the profile when there is
+/// one, and returns the worst exit code.
+fn for_each_input<F: FnMut(&Source) -> i32>(inputs: &Inputs, day: u8, mut f: F) -> i32 {
+    let mut exit_code = 0;
+
+    for input in inputs.resolve(day) {
+        if let Some(ref profile) = input.profile {
+            println!("Day {} ({})", day, profile);
+        }
+        exit_code = exit_code.max(f(&input.source));
+    }
+
+    exit_code
+}
+
+fn profile_suffix(profile: &Option<String>) -> String {
+    profile.as_ref().map(|p| format!(" ({})", p)).unwrap_or_default()
+}
+
+fn select_days(days: &Days) -> Vec<u8> {
+    match *days {
+        Days::All => registry::days().iter().map(|d| d.day()).collect(),
+        Days::Day(day) => vec![day],
+    }
+}
+
+fn load(day: u8, source: &Source) -> Result<(Box<dyn Day>, String), Error> {
+    let solution = registry::find(day).ok_or(Error::UnknownDay(day))?;
+    let contents = source.read()?;
+
+    Ok((solution, contents))
+}
+
+/// Solves the requested parts of a single day, returning each part's answer and timings.
+pub fn solve_day(day: u8, part: Option<u8>, source: &Source) -> Result<DayResult, Error> {
+    let (solution, contents) = load(day, source)?;
+
+    solution.run(&contents, part).map_err(|source| Error::Parse { day, source })
+}
+
+/// Prints why each unanswered part of `result` failed, returning 1 if any did.
+fn report_failures(result: &DayResult) -> i32 {
+    let mut exit_code = 0;
+
+    for part in result.parts.iter() {
+        if let Err(ref source) = part.answer {
+            eprintln!("{}", Error::Solve { day: result.day, part: part.part, source: source.clone() });
+            exit_code = 1;
+        }
+    }
+
+    exit_code
+}
+
+/// Runs the days selected by `options`, printing the answers in the requested format and
+/// returning the process exit code. Text output is printed as each day completes, structured
+/// formats once every day has run.
+pub fn run(options: &RunOptions) -> i32 {
+    let mut exit_code = 0;
+    let mut records = Vec::new();
+
+    for day in select_days(&options.days) {
+        for input in options.inputs.resolve(day) {
+            if options.format == Format::Text {
+                match input.profile {
+                    Some(ref profile) => println!("Day {} ({})", day, profile),
+                    None if options.days == Days::All => println!("Day {}", day),
+                    None => (),
+                }
+            }
+
+            match solve_day(day, options.part, &input.source) {
+                Ok(result) => {
+                    let day_records = Record::from_result(&result, &input.source.to_string());
+
+                    if options.format == Format::Text {
+                        println!("Parsed input in {:.3}ms", millis(result.parse_duration));
+                        print!("{}", output::render(&day_records, Format::Text));
+                    } else {
+                        records.extend(day_records);
+                    }
+                    exit_code = exit_code.max(report_failures(&result));
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    exit_code = 1;
+                }
+            }
+        }
+    }
+
+    if options.format != Format::Text {
+        print!("{}", output::render(&records, options.format));
+    }
+
+    exit_code
+}
+
+/// Checks each selected day against the recorded answers, or records them when `options.record`
+/// is set. Each profile is checked against its own answers. Returns a non-zero exit code if any
+/// part fails or cannot be run.
+pub fn verify(options: &VerifyOptions) -> i32 {
+    let inputs = &options.inputs;
+
+    if inputs.input.is_some() || inputs.profiles.is_empty() {
+        return verify_profile(options, inputs, None);
+    }
+
+    inputs
+        .profiles
+        .iter()
+        .map(|profile| {
+            let single = Inputs { profiles: vec![profile.clone()], ..inputs.clone() };
+            verify_profile(options, &single, Some(profile))
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn verify_profile(options: &VerifyOptions, inputs: &Inputs, profile: Option<&str>) -> i32 {
+    let path = match options.answers {
+        Some(ref path) => path.clone(),
+        None => answers::profile_path(&inputs.dir, profile),
+    };
+    let mut answers = match Answers::load(&path) {
+        Ok(answers) => answers,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    let mut exit_code = 0;
+
+    for day in select_days(&options.days) {
+        for input in inputs.resolve(day) {
+            let results = match solve_day(day, None, &input.source) {
+                Ok(results) => results,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    exit_code = 1;
+                    continue;
+                }
+            };
+            let profile = profile_suffix(&input.profile);
+
+            exit_code = exit_code.max(report_failures(&results));
+
+            for part_result in results.parts {
+                let (part, answer) = match part_result.answer {
+                    Ok(answer) => (part_result.part, answer),
+                    Err(_) => continue,
+                };
+                if options.record {
+                    answers.set(day, part, &answer.value);
+                    println!("Day {} part {}{}: recorded {}", day, part, profile, answer.value);
+                    continue;
+                }
+
+                let status = answers.check(day, part, &answer.value);
+                if let Status::Fail { .. } = status {
+                    exit_code = 1;
+                }
+                println!("Day {} part {}{}: {} ({})", day, part, profile, status, answer.value);
+            }
+        }
+    }
+
+    if options.record {
+        if let Err(error) = answers.save(&path) {
+            eprintln!("{}", error);
+            exit_code = 1;
+        }
+    }
+
+    exit_code
+}
+
+fn print_stats(name: &str, stats: &Stats) {
+    println!(
+        "  {:<7} min {:>10.3}ms  median {:>10.3}ms  max {:>10.3}ms",
+        name, millis(stats.min), millis(stats.median), millis(stats.max)
+    );
+}
+
+/// Benchmarks each selected day, printing min/median/max timings and optionally writing them as
+/// JSON.
+pub fn bench(options: &BenchOptions) -> i32 {
+    let mut exit_code = 0;
+    let mut results = Vec::new();
+
+    for day in select_days(&options.days) {
+        for input in options.inputs.resolve(day) {
+            let result = load(day, &input.source).and_then(|(solution, contents)| {
+                bench::bench_day(solution.as_ref(), &contents, options.part, options.runs)
+                    .map_err(|source| Error::Parse { day, source })
+            });
+
+            match result {
+                Ok(result) => {
+                    println!("Day {}{} ({} runs)", result.day, profile_suffix(&input.profile), result.runs);
+                    print_stats("parse", &result.parse);
+                    for part in result.parts.iter() {
+                        print_stats(&format!("part {}", part.part), &part.stats);
+                    }
+                    results.push(result);
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    exit_code = 1;
+                }
+            }
+        }
+    }
+
+    if let Some(ref path) = options.json {
+        if let Err(source) = fs::write(path, bench::to_json(&results)) {
+            eprintln!("{}", Error::Io { path: path.to_string(), source });
+            exit_code = 1;
+        }
+    }
+
+    exit_code
+}
+
+/// Replays a day 5 polymer collapse: every intermediate polymer for short inputs, otherwise
+/// summary statistics.
+pub fn trace(options: &TraceOptions) -> i32 {
+    match options.polymer {
+        Some(ref polymer) => trace_polymer(options, Ok(polymer.clone())),
+        None => for_each_input(&options.inputs, 5, |source| trace_polymer(options, source.read())),
+    }
+}
+
+fn trace_polymer(options: &TraceOptions, text: Result<String, Error>) -> i32 {
+    // Parsing only validates: the trace runs over the untrimmed text so that reaction positions
+    // refer to the input as given.
+    let parsed = text.and_then(|text| match Day5.parse(&text) {
+        Ok(polymer) => Ok((text, polymer.len())),
+        Err(source) => Err(Error::Parse { day: 5, source }),
+    });
+    let (polymer, units) = match parsed {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    let trace = match options.rules {
+        Some(ref path) => match PairTable::load(path) {
+            Ok(table) => day5::collapse_with_trace_with_rule(&polymer, &table),
+            Err(error) => {
+                eprintln!("{}", error);
+                return 1;
+            }
+        },
+        None => day5::collapse_with_trace(&polymer),
+    };
+
+    if units <= options.limit {
+        for (step, remaining) in trace.replay(&polymer).iter().enumerate() {
+            match step {
+                0 => println!("{:>4}  {}", step, remaining),
+                _ => {
+                    let r = trace.reactions[step - 1];
+                    println!("{:>4}  {}  ({}{} at {},{}; depth {})", step, remaining, r.units.0, r.units.1, r.left, r.right, r.depth);
+                }
+            }
+        }
+    } else {
+        println!("Units: {}", units);
+        println!("Collapsed length: {}", trace.collapsed.len());
+        println!("Reactions: {}", trace.reactions.len());
+        println!("Max stack depth: {}", trace.max_depth);
+        println!("Reactions by unit:");
+        for (unit, count) in trace.reactions_by_unit() {
+            println!("  {}: {}", unit, count);
+        }
+    }
+
+    0
+}
+
+/// Summarises how the day 1 frequency moves, or prints one table of the analysis as CSV.
+pub fn analyse(options: &AnalyseOptions) -> i32 {
+    for_each_input(&options.inputs, 1, |source| analyse_input(options, source))
+}
+
+fn analyse_input(options: &AnalyseOptions, source: &Source) -> i32 {
+    let changes = match source.read().and_then(|text| Day1.parse(&text).map_err(|source| Error::Parse { day: 1, source })) {
+        Ok(changes) => changes,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    let analysis = match day1::analyse(&changes, options.repeats) {
+        Ok(analysis) => analysis,
+        Err(overflow) => {
+            eprintln!("{}", overflow);
+            return 1;
+        }
+    };
+
+    match options.export {
+        Some(Table::Series) => {
+            println!("step,frequency");
+            for (step, frequency) in analysis.series.iter().enumerate() {
+                println!("{},{}", step, frequency);
+            }
+        }
+        Some(Table::Repeats) => {
+            println!("frequency,iteration,index");
+            for r in analysis.repeats.iter() {
+                println!("{},{},{}", r.frequency, r.iteration, r.index);
+            }
+        }
+        Some(Table::Histogram) => {
+            println!("change,count");
+            for (change, count) in analysis.histogram.iter() {
+                println!("{},{}", change, count);
+            }
+        }
+        None => {
+            println!("Changes: {}", changes.len());
+            println!("Drift per pass: {}", analysis.drift);
+            println!("Range over first pass: {} to {}", analysis.min, analysis.max);
+            println!("First {} repeats:", analysis.repeats.len());
+            for r in analysis.repeats.iter() {
+                println!("  {} (pass {}, change {})", r.frequency, r.iteration, r.index);
+            }
+            if let Some(overflow) = analysis.overflow {
+                println!("  {}", overflow);
+            }
+            println!("Changes by value:");
+            for (change, count) in analysis.histogram.iter() {
+                println!("  {:>+6}: {}", change, count);
+            }
+        }
+    }
+
+    0
+}
+
+/// Groups the day 2 box IDs into clusters linked by single character differences.
+pub fn cluster(options: &ClusterOptions) -> i32 {
+    for_each_input(&options.inputs, 2, |source| cluster_input(options, source))
+}
+
+fn cluster_input(options: &ClusterOptions, source: &Source) -> i32 {
+    let ids = match source.read().and_then(|text| Day2.parse(&text).map_err(|source| Error::Parse { day: 2, source })) {
+        Ok(ids) => ids,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    let clustering = day2::cluster(&ids);
+
+    match options.format {
+        ClusterFormat::Json => print!("{}", day2::cluster::to_json(&clustering, options.min_size)),
+        ClusterFormat::Dot => print!("{}", day2::cluster::to_dot(&clustering, options.min_size)),
+        ClusterFormat::Text => {
+            println!("IDs: {}", ids.len());
+            println!("Clusters: {}", clustering.clusters.len());
+            for c in clustering.clusters.iter().filter(|c| c.size() >= options.min_size) {
+                println!("  {} IDs, e.g. {} (common: {})", c.size(), c.representative(), c.common);
+            }
+        }
+    }
+
+    0
+}
+
+/// Lays the day 3 claims over the fabric with the chosen engine and summarises the overlaps.
+pub fn coverage(options: &CoverageOptions) -> i32 {
+    for_each_input(&options.inputs, 3, |source| coverage_input(options, source))
+}
+
+fn coverage_input(options: &CoverageOptions, source: &Source) -> i32 {
+    let claims = match source.read().and_then(|text| Day3.parse(&text).map_err(|source| Error::Parse { day: 3, source })) {
+        Ok(claims) => claims.claims,
+        Err(error) => {
+            eprintln!("{}", error);
+            return 1;
+        }
+    };
+
+    let coverage = if options.depths {
+        day3::coverage_by_depth(&claims, options.engine)
+    } else {
+        day3::coverage(&claims, options.engine)
+    };
+
+    println!("Claims: {}", claims.len());
+    println!("Contested area: {}", coverage.contested_area);
+    println!("Unique claims: {}", coverage.unique_claims.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "));
+    if let Some(ref areas) = coverage.area_by_depth {
+        println!("Area by depth:");
+        for (depth, area) in areas.iter() {
+            println!("  {:>4}: {}", depth, area);
+        }
+    }
+
+    0
+}
@@ -0,0 +1,140 @@
+//! The interface every day implements.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use error::{ParseError, SolveError};
+
+/// A labelled answer to one part of a puzzle.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Answer {
+    pub label: &'static str,
+    pub value: String,
+}
+
+impl Answer {
+    pub fn new<T: fmt::Display>(label: &'static str, value: T) -> Answer {
+        Answer { label, value: value.to_string() }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.label, self.value)
+    }
+}
+
+/// A day's puzzle: parses the raw input once, then solves each part from the parsed form.
+pub trait Solution {
+    type Input;
+
+    fn day(&self) -> u8;
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError>;
+    fn part_1(&self, input: &Self::Input) -> Result<Answer, SolveError>;
+    fn part_2(&self, input: &Self::Input) -> Result<Answer, SolveError>;
+}
+
+/// The answer to one part, or why there is none, along with how long it took to solve.
+#[derive(Debug, Clone)]
+pub struct PartResult {
+    pub part: u8,
+    pub answer: Result<Answer, SolveError>,
+    pub duration: Duration,
+}
+
+/// The results of running a day, including the time spent parsing its input.
+#[derive(Debug, Clone)]
+pub struct DayResult {
+    pub day: u8,
+    pub parse_duration: Duration,
+    pub parts: Vec<PartResult>,
+}
+
+/// Type-erased view of a `Solution` so days with different input types can share the registry.
+pub trait Day {
+    fn day(&self) -> u8;
+    /// Parses `input` and solves the requested part, or both when `part` is `None`.
+    fn run(&self, input: &str, part: Option<u8>) -> Result<DayResult, ParseError>;
+}
+
+fn timed<T, F: FnOnce() -> T>(f: F) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+impl<S: Solution> Day for S {
+    fn day(&self) -> u8 {
+        Solution::day(self)
+    }
+
+    fn run(&self, input: &str, part: Option<u8>) -> Result<DayResult, ParseError> {
+        let (parsed, parse_duration) = timed(|| self.parse(input));
+        let parsed = parsed?;
+        let mut parts = Vec::new();
+
+        if part.is_none() || part == Some(1) {
+            let (answer, duration) = timed(|| self.part_1(&parsed));
+            parts.push(PartResult { part: 1, answer, duration });
+        }
+        if part.is_none() || part == Some(2) {
+            let (answer, duration) = timed(|| self.part_2(&parsed));
+            parts.push(PartResult { part: 2, answer, duration });
+        }
+
+        Ok(DayResult { day: Solution::day(self), parse_duration, parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Example;
+
+    impl Solution for Example {
+        type Input = Vec<i32>;
+
+        fn day(&self) -> u8 { 99 }
+
+        fn parse(&self, input: &str) -> Result<Vec<i32>, ParseError> {
+            input.split(',').filter(|x| !x.is_empty()).map(|x| x.parse().map_err(|_| ParseError::new(1, x, "not a number"))).collect()
+        }
+
+        fn part_1(&self, input: &Vec<i32>) -> Result<Answer, SolveError> {
+            Ok(Answer::new("Sum", input.iter().sum::<i32>()))
+        }
+
+        fn part_2(&self, input: &Vec<i32>) -> Result<Answer, SolveError> {
+            input.iter().max().map(|max| Answer::new("Max", max)).ok_or_else(|| SolveError::new("no numbers"))
+        }
+    }
+
+    fn answers(result: DayResult) -> Vec<(u8, Result<Answer, SolveError>)> {
+        result.parts.into_iter().map(|p| (p.part, p.answer)).collect()
+    }
+
+    #[test]
+    fn should_run_selected_parts() {
+        let day: &dyn Day = &Example;
+
+        assert_eq!(
+            vec![(1, Ok(Answer::new("Sum", 6))), (2, Ok(Answer::new("Max", 3)))],
+            answers(day.run("1,2,3", None).unwrap())
+        );
+        assert_eq!(vec![(2, Ok(Answer::new("Max", 3)))], answers(day.run("1,2,3", Some(2)).unwrap()));
+        assert_eq!("Sum: 6", day.run("1,2,3", Some(1)).unwrap().parts[0].answer.as_ref().unwrap().to_string());
+        assert_eq!(99, day.run("1,2,3", None).unwrap().day);
+        assert_eq!(Some(ParseError::new(1, "x", "not a number")), day.run("1,x", None).err());
+    }
+
+    #[test]
+    fn should_report_unanswered_parts() {
+        let day: &dyn Day = &Example;
+
+        assert_eq!(
+            vec![(1, Ok(Answer::new("Sum", 0))), (2, Err(SolveError::new("no numbers")))],
+            answers(day.run("", None).unwrap())
+        );
+    }
+}
@@ -0,0 +1,54 @@
+extern crate advent_of_code_2018;
+
+use std::fs;
+
+use advent_of_code_2018::answers::{self, Answers, Status};
+use advent_of_code_2018::registry;
+
+fn answers(day: u8) -> Vec<String> {
+    let input = fs::read_to_string(format!("resources/day{}.txt", day)).expect("Failed to load input");
+    let solution = registry::find(day).expect("Day not registered");
+
+    solution.run(&input, None)
+        .expect("Failed to parse input")
+        .parts
+        .into_iter()
+        .map(|part| part.answer.expect("Failed to solve part").value)
+        .collect()
+}
+
+#[test]
+fn day_1() {
+    assert_eq!(vec!["556", "448"], answers(1));
+}
+
+#[test]
+fn day_2() {
+    assert_eq!(vec!["7808", "efmyhuckqldtwjyvisipargno"], answers(2));
+}
+
+#[test]
+fn day_3() {
+    assert_eq!(vec!["100595", "415"], answers(3));
+}
+
+#[test]
+fn day_4() {
+    assert_eq!(vec!["4716", "117061"], answers(4));
+}
+
+#[test]
+fn day_5() {
+    assert_eq!(vec!["10368", "4122"], answers(5));
+}
+
+#[test]
+fn recorded_answers_match() {
+    let recorded = Answers::load(answers::DEFAULT_PATH).expect("Failed to load answers");
+
+    for solution in registry::days() {
+        for (part, value) in answers(solution.day()).iter().enumerate() {
+            assert_eq!(Status::Pass, recorded.check(solution.day(), part as u8 + 1, value));
+        }
+    }
+}